@@ -1,19 +1,24 @@
 // src/aabb.rs
 use crate::math::Vec3;
 use crate::ray::Ray;
+use crate::shape::Shape;
 
 pub struct Hit {
     pub t: f32,
     pub p: Vec3,
     pub n: Vec3,
     pub mat_id: usize,
+    // coordenadas de superficie, para las texturas
+    pub uv: [f32; 2],
 }
 
 #[derive(Copy, Clone)]
 pub struct Aabb { pub min: Vec3, pub max: Vec3, pub mat_id: usize }
 
-impl Aabb {
-    pub fn hit(&self, ray: &Ray, mut tmin: f32, mut tmax: f32) -> Option<Hit> {
+impl Shape for Aabb {
+    fn hit(&self, ray: &Ray, mut tmin: f32, mut tmax: f32) -> Option<Hit> {
+        let (t_lo, t_hi) = (tmin, tmax);
+
         // X axis
         let inv_dx = 1.0 / ray.d.x;
         let mut tx0 = (self.min.x - ray.o.x) * inv_dx;
@@ -41,7 +46,9 @@ impl Aabb {
         tmax = tmax.min(tz1);
         if tmax <= tmin { return None; }
 
-        let t = tmin;
+        // Si el origen está dentro de la caja, el impacto es la salida
+        // (rayos refractados que cruzan un vidrio, por ejemplo)
+        let t = if tmin > t_lo { tmin } else if tmax < t_hi { tmax } else { return None; };
         let p = ray.at(t);
 
         // normal por cara (epsilon)
@@ -54,6 +61,49 @@ impl Aabb {
         else if (p.z - self.min.z).abs() < eps { n = Vec3::new(0.0, 0.0,-1.0); }
         else if (p.z - self.max.z).abs() < eps { n = Vec3::new(0.0, 0.0, 1.0); }
 
-        Some(Hit { t, p, n, mat_id: self.mat_id })
+        // uv: las otras dos coordenadas de la cara, llevadas a [0,1]
+        let e = self.max - self.min;
+        let r = Vec3::new((p.x - self.min.x) / e.x.max(1e-8), (p.y - self.min.y) / e.y.max(1e-8), (p.z - self.min.z) / e.z.max(1e-8));
+        let uv = if n.x != 0.0 { [r.z, r.y] } else if n.y != 0.0 { [r.x, r.z] } else { [r.x, r.y] };
+
+        Some(Hit { t, p, n, mat_id: self.mat_id, uv })
     }
-}
\ No newline at end of file
+
+    fn bounds(&self) -> Option<Aabb> { Some(*self) }
+
+    fn mat_id(&self) -> usize { self.mat_id }
+
+    fn area(&self) -> f32 {
+        let e = self.max - self.min;
+        2.0 * (e.x*e.y + e.y*e.z + e.z*e.x)
+    }
+
+    // u.x elige la cara, u.y/u.z la posición en ella
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3) {
+        let e = self.max - self.min;
+        let (axy, ayz, azx) = (e.x*e.y, e.y*e.z, e.z*e.x);
+        let total = axy + ayz + azx;
+        let mut pick = u.x * total;
+        if pick < axy {
+            // caras z
+            let back = pick < axy * 0.5;
+            let z = if back { self.min.z } else { self.max.z };
+            let p = Vec3::new(self.min.x + u.y*e.x, self.min.y + u.z*e.y, z);
+            return (p, Vec3::new(0.0, 0.0, if back { -1.0 } else { 1.0 }));
+        }
+        pick -= axy;
+        if pick < ayz {
+            // caras x
+            let back = pick < ayz * 0.5;
+            let x = if back { self.min.x } else { self.max.x };
+            let p = Vec3::new(x, self.min.y + u.y*e.y, self.min.z + u.z*e.z);
+            return (p, Vec3::new(if back { -1.0 } else { 1.0 }, 0.0, 0.0));
+        }
+        pick -= ayz;
+        // caras y
+        let back = pick < azx * 0.5;
+        let y = if back { self.min.y } else { self.max.y };
+        let p = Vec3::new(self.min.x + u.y*e.x, y, self.min.z + u.z*e.z);
+        (p, Vec3::new(0.0, if back { -1.0 } else { 1.0 }, 0.0))
+    }
+}
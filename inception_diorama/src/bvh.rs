@@ -0,0 +1,218 @@
+// src/bvh.rs
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::shape::Shape;
+
+// Nodo plano: si count > 0 es hoja con prims [first, first+count),
+// si no, los hijos son `first` y `first+1`.
+#[derive(Copy, Clone)]
+struct Node { min: Vec3, max: Vec3, first: u32, count: u32 }
+
+#[derive(Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    idx: Vec<u32>, // índices a los prims ordenados por hoja
+    unbounded: Vec<u32>, // prims sin caja (planos), se prueban siempre
+}
+
+const MAX_LEAF: usize = 4;
+const COST_TRAV: f32 = 1.0;
+const COST_HIT: f32 = 1.0;
+
+fn vmin(a:Vec3, b:Vec3)->Vec3{ Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)) }
+fn vmax(a:Vec3, b:Vec3)->Vec3{ Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)) }
+fn axis(p:Vec3, a:usize)->f32{ match a { 0 => p.x, 1 => p.y, _ => p.z } }
+
+fn area(min:Vec3, max:Vec3)->f32{
+    let e = max - min;
+    if e.x < 0.0 { return 0.0; } // caja vacía
+    2.0 * (e.x*e.y + e.y*e.z + e.z*e.x)
+}
+
+// Slab test contra el nodo; devuelve la t de entrada
+fn node_hit(n:&Node, o:Vec3, inv:Vec3, tmin:f32, tmax:f32)->Option<f32>{
+    let (mut t0, mut t1) = (tmin, tmax);
+    for a in 0..3 {
+        let mut ta = (axis(n.min,a) - axis(o,a)) * axis(inv,a);
+        let mut tb = (axis(n.max,a) - axis(o,a)) * axis(inv,a);
+        if axis(inv,a) < 0.0 { std::mem::swap(&mut ta, &mut tb); }
+        t0 = t0.max(ta);
+        t1 = t1.min(tb);
+        if t1 < t0 { return None; }
+    }
+    Some(t0)
+}
+
+impl Bvh {
+    pub fn build<S: Shape>(prims: &[S]) -> Self {
+        let bounds: Vec<Option<Aabb>> = prims.iter().map(|p| p.bounds()).collect();
+        let (idx, unbounded) = (0..prims.len() as u32).partition(|&i| bounds[i as usize].is_some());
+        let mut bvh = Bvh { nodes: Vec::new(), idx, unbounded };
+        if bvh.idx.is_empty() { return bvh; }
+        let boxes: Vec<Aabb> = bounds.iter()
+            .map(|b| b.unwrap_or(Aabb { min: Vec3::default(), max: Vec3::default(), mat_id: 0 }))
+            .collect();
+        let cent: Vec<Vec3> = boxes.iter().map(|b| (b.min + b.max) * 0.5).collect();
+        bvh.nodes.push(Node { min: Vec3::default(), max: Vec3::default(), first: 0, count: bvh.idx.len() as u32 });
+        bvh.subdivide(0, &boxes, &cent);
+        bvh
+    }
+
+    fn subdivide(&mut self, ni: usize, prims: &[Aabb], cent: &[Vec3]) {
+        let (first, count) = (self.nodes[ni].first as usize, self.nodes[ni].count as usize);
+        let items = &mut self.idx[first..first+count];
+
+        let (mut bmin, mut bmax) = (v_inf(), -v_inf());
+        for &i in items.iter() { bmin = vmin(bmin, prims[i as usize].min); bmax = vmax(bmax, prims[i as usize].max); }
+        self.nodes[ni].min = bmin;
+        self.nodes[ni].max = bmax;
+        if count <= 2 { return; }
+
+        // SAH por barrido completo en cada eje
+        let mut best: Option<(usize, usize, f32)> = None; // (eje, corte, costo)
+        let mut right_area = vec![0.0f32; count];
+        for a in 0..3 {
+            items.sort_by(|&i, &j| axis(cent[i as usize],a).total_cmp(&axis(cent[j as usize],a)).then(i.cmp(&j)));
+            let (mut rmin, mut rmax) = (v_inf(), -v_inf());
+            for k in (1..count).rev() {
+                let b = &prims[items[k] as usize];
+                rmin = vmin(rmin, b.min); rmax = vmax(rmax, b.max);
+                right_area[k] = area(rmin, rmax);
+            }
+            let (mut lmin, mut lmax) = (v_inf(), -v_inf());
+            for k in 1..count {
+                let b = &prims[items[k-1] as usize];
+                lmin = vmin(lmin, b.min); lmax = vmax(lmax, b.max);
+                let cost = area(lmin, lmax) * k as f32 + right_area[k] * (count - k) as f32;
+                if best.is_none_or(|(_, _, c)| cost < c) { best = Some((a, k, cost)); }
+            }
+        }
+
+        let (a, split, cost) = best.unwrap();
+        let parent_area = area(bmin, bmax).max(1e-12);
+        let split_cost = COST_TRAV + COST_HIT * cost / parent_area;
+        if count <= MAX_LEAF && split_cost >= COST_HIT * count as f32 { return; }
+
+        items.sort_by(|&i, &j| axis(cent[i as usize],a).total_cmp(&axis(cent[j as usize],a)).then(i.cmp(&j)));
+        let left = self.nodes.len();
+        self.nodes.push(Node { min: bmin, max: bmax, first: first as u32, count: split as u32 });
+        self.nodes.push(Node { min: bmin, max: bmax, first: (first+split) as u32, count: (count-split) as u32 });
+        self.nodes[ni].first = left as u32;
+        self.nodes[ni].count = 0;
+        self.subdivide(left, prims, cent);
+        self.subdivide(left+1, prims, cent);
+    }
+
+    // Impacto más cercano; en empates gana el prim de menor índice,
+    // igual que el recorrido lineal en orden.
+    pub fn closest<S: Shape>(&self, prims: &[S], ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        let mut best: Option<(Hit, u32)> = None;
+        let mut far = tmax;
+        let mut test = |i: u32, far: &mut f32| {
+            // next_up para no perder empates exactos con `far`
+            if let Some(h) = prims[i as usize].hit(ray, tmin, far.next_up()) {
+                let better = match &best {
+                    None => h.t < *far,
+                    Some((_, bi)) => h.t < *far || i < *bi,
+                };
+                if better { *far = h.t; best = Some((h, i)); }
+            }
+        };
+        for &i in &self.unbounded { test(i, &mut far); }
+        let inv = Vec3::new(1.0/ray.d.x, 1.0/ray.d.y, 1.0/ray.d.z);
+        let mut stack: Vec<u32> = Vec::with_capacity(64);
+        if !self.nodes.is_empty() { stack.push(0); }
+        while let Some(ni) = stack.pop() {
+            let n = &self.nodes[ni as usize];
+            if node_hit(n, ray.o, inv, tmin, far).is_none() { continue; }
+            if n.count > 0 {
+                for &i in &self.idx[n.first as usize..(n.first+n.count) as usize] { test(i, &mut far); }
+            } else {
+                // visitar primero el hijo más cercano
+                let (l, r) = (n.first, n.first + 1);
+                let tl = node_hit(&self.nodes[l as usize], ray.o, inv, tmin, far);
+                let tr = node_hit(&self.nodes[r as usize], ray.o, inv, tmin, far);
+                match (tl, tr) {
+                    (Some(a), Some(b)) => {
+                        let (near, far_c) = if a <= b { (l, r) } else { (r, l) };
+                        stack.push(far_c); stack.push(near);
+                    }
+                    (Some(_), None) => stack.push(l),
+                    (None, Some(_)) => stack.push(r),
+                    (None, None) => {}
+                }
+            }
+        }
+        best.map(|(h, _)| h)
+    }
+
+    // Cualquier impacto en (tmin, tmax): para rayos de sombra
+    pub fn any<S: Shape>(&self, prims: &[S], ray: &Ray, tmin: f32, tmax: f32) -> bool {
+        self.any_where(prims, ray, tmin, tmax, |_| true)
+    }
+
+    // Cualquier impacto que cumpla `keep`; de cada primitiva se mira solo el más cercano
+    pub fn any_where<S: Shape>(&self, prims: &[S], ray: &Ray, tmin: f32, tmax: f32, keep: impl Fn(&Hit) -> bool) -> bool {
+        let hit = |i: u32| prims[i as usize].hit(ray, tmin, tmax).is_some_and(|h| keep(&h));
+        if self.unbounded.iter().any(|&i| hit(i)) { return true; }
+        if self.nodes.is_empty() { return false; }
+        let inv = Vec3::new(1.0/ray.d.x, 1.0/ray.d.y, 1.0/ray.d.z);
+        let mut stack: Vec<u32> = Vec::with_capacity(64);
+        stack.push(0);
+        while let Some(ni) = stack.pop() {
+            let n = &self.nodes[ni as usize];
+            if node_hit(n, ray.o, inv, tmin, tmax).is_none() { continue; }
+            if n.count > 0 {
+                for &i in &self.idx[n.first as usize..(n.first+n.count) as usize] {
+                    if hit(i) { return true; }
+                }
+            } else {
+                stack.push(n.first); stack.push(n.first + 1);
+            }
+        }
+        false
+    }
+}
+
+fn v_inf()->Vec3{ Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::scene::Scene;
+
+    // Lo que el BVH reemplazó: probar todo, uno por uno
+    fn linear<S: Shape>(prims: &[S], ray: &Ray, tmin: f32, tmax: f32) -> Option<f32> {
+        prims.iter().filter_map(|p| p.hit(ray, tmin, tmax)).map(|h| h.t).min_by(f32::total_cmp)
+    }
+
+    // Los mismos impactos que el recorrido lineal, para los rayos de cámara
+    // de la escena de prueba y para tramos cualquiera (los de sombra)
+    #[test]
+    fn same_hits_as_linear_scan() {
+        let scene = Scene::test_scene();
+        let bvh = Bvh::build(&scene.objects);
+        let cam = Camera::from_euler(Vec3::new(0.0, 0.0, 12.0), 0.0, 0.0, 0.0, 60.0, 16.0 / 9.0);
+        let (w, h) = (48, 27);
+        for j in 0..h {
+            for i in 0..w {
+                let ray = cam.ray_for((i as f32 + 0.5) / w as f32 * 2.0 - 1.0, 1.0 - (j as f32 + 0.5) / h as f32 * 2.0);
+                let got = bvh.closest(&scene.objects, &ray, 0.001, 1e9).map(|h| h.t);
+                assert_eq!(got, linear(&scene.objects, &ray, 0.001, 1e9), "píxel ({i}, {j})");
+            }
+        }
+        // orígenes y direcciones desparramados por la escena, sin azar
+        for k in 0..2000 {
+            let f = k as f32;
+            let o = Vec3::new((f * 1.37).sin() * 15.0, (f * 0.71).sin() * 3.0 - 1.0, (f * 1.13).cos() * 15.0);
+            let d = Vec3::new((f * 2.31).sin(), (f * 1.73).cos(), (f * 0.97).sin()).norm();
+            let ray = Ray::new(o, d);
+            let tmax = (f * 0.53).sin().abs() * 20.0;
+            let expected = linear(&scene.objects, &ray, 0.001, tmax);
+            assert_eq!(bvh.closest(&scene.objects, &ray, 0.001, tmax).map(|h| h.t), expected);
+            assert_eq!(bvh.any(&scene.objects, &ray, 0.001, tmax), expected.is_some());
+        }
+    }
+}
@@ -1,6 +1,21 @@
 // src/camera.rs
 use crate::math::Vec3;
 
+// Pose con nombre guardada en la escena
+#[derive(Clone)]
+pub struct CameraPreset {
+    pub name: String,
+    pub eye: Vec3,
+    pub yaw: f32, pub pitch: f32, pub roll: f32,
+    pub fov: f32,
+}
+
+impl Default for CameraPreset {
+    fn default() -> Self {
+        Self { name: "default".to_string(), eye: Vec3::new(0.0, 0.0, 12.0), yaw: 0.0, pitch: 0.0, roll: 0.0, fov: 60.0 }
+    }
+}
+
 pub struct Camera {
     pub origin: Vec3,
     pub u: Vec3, pub v: Vec3, pub w: Vec3, // base de cámara
@@ -36,6 +51,6 @@ impl Camera {
     pub fn ray_for(&self, x:f32, y:f32)->crate::ray::Ray{
         // x,y en [-1,1]
         let dir = (self.u * (x*self.half_w) + self.v * (y*self.half_h) - self.w).norm();
-        crate::ray::Ray{ o: self.origin, d: dir }
+        crate::ray::Ray::new(self.origin, dir)
     }
 }
\ No newline at end of file
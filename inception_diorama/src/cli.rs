@@ -0,0 +1,141 @@
+// src/cli.rs
+use crate::math::Vec3;
+use crate::camera::CameraPreset;
+use crate::tonemap::ToneMap;
+
+pub const USAGE: &str = "\
+uso: inception_diorama [opciones]
+  --scene ARCHIVO       escena en texto (por defecto la de prueba)
+  --save-scene ARCHIVO  escribe la escena cargada y termina
+  --headless            renderiza a archivo sin abrir ventana
+  --width N --height N  resolución (640x360)
+  --camera NOMBRE       cámara guardada en la escena (la primera)
+  --eye x,y,z           posición de la cámara
+  --yaw G --pitch G --roll G   orientación en grados
+  --fov G               campo de visión vertical
+  --time H              hora del día (0-24); activa el cielo con sol
+  --spp N               muestras por píxel (1)
+  --path                path tracing en vez de Whitted
+  --bounces N           rebotes máximos del path tracer (8)
+  --tonemap NOMBRE      aces, reinhard o clamp (aces)
+  --exposure EV         exposición en pasos (0)
+  --out ARCHIVO         salida .png o .ppm (render.png)
+  --threads N           hilos de render (0 = todos)";
+
+pub struct Options {
+    pub scene: Option<String>,
+    pub save_scene: Option<String>,
+    pub headless: bool,
+    pub width: usize,
+    pub height: usize,
+    pub camera: Option<String>,
+    // sobrescriben la cámara elegida
+    pub eye: Option<Vec3>,
+    pub yaw: Option<f32>,
+    pub pitch: Option<f32>,
+    pub roll: Option<f32>,
+    pub fov: Option<f32>,
+    pub time: Option<f32>,
+    pub spp: u32,
+    pub path: bool,
+    pub bounces: u32,
+    pub tonemap: ToneMap,
+    pub exposure: f32,
+    pub out: String,
+    pub threads: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            scene: None,
+            save_scene: None,
+            headless: false,
+            width: 640, height: 360,
+            camera: None,
+            eye: None,
+            yaw: None, pitch: None, roll: None,
+            fov: None,
+            time: None,
+            spp: 1,
+            path: false,
+            bounces: 8,
+            tonemap: ToneMap::Aces,
+            exposure: 0.0,
+            out: "render.png".to_string(),
+            threads: 0,
+        }
+    }
+}
+
+fn num<T: std::str::FromStr>(flag: &str, s: Option<&String>) -> Result<T, String> {
+    let s = s.ok_or(format!("{flag} espera un valor"))?;
+    s.parse().map_err(|_| format!("{flag}: valor inválido '{s}'"))
+}
+
+fn word(flag: &str, s: Option<&String>) -> Result<String, String> {
+    s.cloned().ok_or(format!("{flag} espera un valor"))
+}
+
+fn vec3(flag: &str, s: Option<&String>) -> Result<Vec3, String> {
+    let s = s.ok_or(format!("{flag} espera x,y,z"))?;
+    let p: Vec<f32> = s.split(',').map(|c| c.trim().parse()).collect::<Result<_,_>>()
+        .map_err(|_| format!("{flag}: valor inválido '{s}'"))?;
+    match p[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err(format!("{flag} espera x,y,z")),
+    }
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut o = Options::default();
+        let mut it = args.iter();
+        while let Some(a) = it.next() {
+            match a.as_str() {
+                "--scene"      => o.scene = Some(word(a, it.next())?),
+                "--save-scene" => o.save_scene = Some(word(a, it.next())?),
+                "--headless"   => o.headless = true,
+                "--width"      => o.width = num(a, it.next())?,
+                "--height"     => o.height = num(a, it.next())?,
+                "--camera"     => o.camera = Some(word(a, it.next())?),
+                "--eye"        => o.eye = Some(vec3(a, it.next())?),
+                "--yaw"        => o.yaw = Some(num(a, it.next())?),
+                "--pitch"      => o.pitch = Some(num(a, it.next())?),
+                "--roll"       => o.roll = Some(num(a, it.next())?),
+                "--fov"        => o.fov = Some(num(a, it.next())?),
+                "--time"       => o.time = Some(num(a, it.next())?),
+                "--spp"        => o.spp = num(a, it.next())?,
+                "--path"       => o.path = true,
+                "--bounces"    => o.bounces = num(a, it.next())?,
+                "--tonemap"    => {
+                    let name = word(a, it.next())?;
+                    o.tonemap = ToneMap::from_name(&name).ok_or(format!("--tonemap: curva desconocida '{name}'"))?;
+                }
+                "--exposure"   => o.exposure = num(a, it.next())?,
+                "--out"        => o.out = word(a, it.next())?,
+                "--threads"    => o.threads = num(a, it.next())?,
+                _ => return Err(format!("opción desconocida '{a}'")),
+            }
+        }
+        if o.width == 0 || o.height == 0 { return Err("la resolución debe ser mayor que 0".into()); }
+        if o.spp == 0 { return Err("--spp debe ser al menos 1".into()); }
+        Ok(o)
+    }
+
+    // Cámara inicial: la pedida con --camera (o la primera de la escena)
+    // con los valores sueltos de la línea de comandos encima.
+    pub fn camera_pose(&self, presets: &[CameraPreset]) -> Result<CameraPreset, String> {
+        let mut c = match &self.camera {
+            Some(name) => presets.iter().find(|c| &c.name == name).cloned()
+                .ok_or(format!("la escena no tiene la cámara '{name}'"))?,
+            None => presets.first().cloned().unwrap_or_default(),
+        };
+        if let Some(eye) = self.eye { c.eye = eye; }
+        if let Some(yaw) = self.yaw { c.yaw = yaw; }
+        if let Some(pitch) = self.pitch { c.pitch = pitch; }
+        if let Some(roll) = self.roll { c.roll = roll; }
+        if let Some(fov) = self.fov { c.fov = fov; }
+        Ok(c)
+    }
+}
@@ -0,0 +1,108 @@
+// src/csg.rs
+// Geometría sólida constructiva: formas convexas y cerradas (cajas,
+// esferas, cilindros) que se van combinando en orden con unión,
+// intersección o resta. Cada forma da el tramo del rayo que pasa por
+// adentro; los tramos se combinan barriendo sus bordes de menor a mayor t.
+// En una resta, las caras talladas son las de lo que se resta, con la
+// normal dada vuelta y el material de lo que se talla.
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::shape::{Object, Shape};
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum CsgOp { Union, Intersect, Subtract }
+
+pub struct Csg {
+    pub steps: Vec<(CsgOp, Object)>, // el primero siempre es una unión
+    bounds: Aabb,
+}
+
+// Por dónde entra y sale el rayo de un sólido
+type Span = (Hit, Hit);
+
+// El tramo de una forma convexa en toda la recta: la entrada es el primer
+// impacto y la salida, el siguiente
+fn span(o: &Object, ray: &Ray) -> Option<Span> {
+    let enter = o.hit(ray, f32::NEG_INFINITY, f32::INFINITY)?;
+    let exit = o.hit(ray, enter.t, f32::INFINITY)?;
+    Some((enter, exit))
+}
+
+fn vmin(a: Vec3, b: Vec3) -> Vec3 { Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)) }
+fn vmax(a: Vec3, b: Vec3) -> Vec3 { Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)) }
+
+fn combine(op: CsgOp, a: Vec<Span>, b: Vec<Span>) -> Vec<Span> {
+    // (borde, es de b, entra)
+    let mut edges: Vec<(Hit, bool, bool)> = Vec::with_capacity(2 * (a.len() + b.len()));
+    for (spans, from_b) in [(a, false), (b, true)] {
+        for (enter, exit) in spans {
+            edges.push((enter, from_b, true));
+            edges.push((exit, from_b, false));
+        }
+    }
+    edges.sort_by(|x, y| x.0.t.total_cmp(&y.0.t));
+
+    let (mut in_a, mut in_b, mut inside, mut mat_a) = (false, false, false, 0);
+    let (mut out, mut start) = (Vec::new(), None);
+    for (mut h, from_b, enter) in edges {
+        if from_b { in_b = enter } else { in_a = enter; mat_a = h.mat_id; }
+        let now = match op {
+            CsgOp::Union => in_a || in_b,
+            CsgOp::Intersect => in_a && in_b,
+            CsgOp::Subtract => in_a && !in_b,
+        };
+        if now == inside { continue; }
+        inside = now;
+        if op == CsgOp::Subtract && from_b {
+            h.n = -h.n;
+            h.mat_id = mat_a;
+        }
+        if now { start = Some(h); } else if let Some(s) = start.take() { out.push((s, h)); }
+    }
+    out
+}
+
+impl Csg {
+    // None si las intersecciones no dejan nada
+    pub fn new(steps: Vec<(CsgOp, Object)>) -> Option<Self> {
+        let mut b = steps[0].1.bounds()?;
+        for (op, o) in &steps[1..] {
+            let c = o.bounds()?;
+            match op {
+                CsgOp::Union => (b.min, b.max) = (vmin(b.min, c.min), vmax(b.max, c.max)),
+                CsgOp::Intersect => (b.min, b.max) = (vmax(b.min, c.min), vmin(b.max, c.max)),
+                // lo que se resta no agranda nada
+                CsgOp::Subtract => {}
+            }
+        }
+        let empty = b.min.x > b.max.x || b.min.y > b.max.y || b.min.z > b.max.z;
+        (!empty).then_some(Self { steps, bounds: b })
+    }
+}
+
+impl Shape for Csg {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        let mut spans: Vec<Span> = span(&self.steps[0].1, ray).into_iter().collect();
+        for (op, o) in &self.steps[1..] {
+            // sin nada que cortar o restar, no hay nada que hacer
+            if spans.is_empty() && *op != CsgOp::Union { continue; }
+            match span(o, ray) {
+                Some(s) => spans = combine(*op, spans, vec![s]),
+                None if *op == CsgOp::Intersect => spans.clear(),
+                None => {}
+            }
+        }
+        // el primer borde dentro de (tmin, tmax): una entrada, o la salida si se está adentro
+        spans.into_iter().flat_map(|(a, b)| [a, b]).find(|h| h.t > tmin && h.t < tmax)
+    }
+
+    fn bounds(&self) -> Option<Aabb> { Some(self.bounds) }
+
+    fn mat_id(&self) -> usize { self.steps[0].1.mat_id() }
+
+    // Como las instancias y los bloques: no se muestrea como luz
+    fn area(&self) -> f32 { 0.0 }
+
+    fn sample_surface(&self, _u: Vec3) -> (Vec3, Vec3) { (Vec3::default(), Vec3::new(0.0, 1.0, 0.0)) }
+}
@@ -0,0 +1,105 @@
+// src/cylinder.rs
+use std::f32::consts::PI;
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::rng::onb;
+use crate::shape::Shape;
+
+// Cilindro con tapas, de `a` a `b`
+#[derive(Copy, Clone)]
+pub struct Cylinder { pub a: Vec3, pub b: Vec3, pub radius: f32, pub mat_id: usize }
+
+impl Cylinder {
+    // Eje unitario, alto y base (u, v) perpendicular al eje
+    fn frame(&self) -> (Vec3, f32, Vec3, Vec3) {
+        let axis = self.b - self.a;
+        let h = axis.len().max(1e-8);
+        let d = axis / h;
+        let (u, v) = onb(d);
+        (d, h, u, v)
+    }
+}
+
+impl Shape for Cylinder {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        let (d, h, u, v) = self.frame();
+        let r = self.radius;
+        // el rayo en coordenadas del cilindro: (x, y) en la base, z por el eje
+        let o = ray.o - self.a;
+        let (ox, oy, oz) = (o.dot(u), o.dot(v), o.dot(d));
+        let (dx, dy, dz) = (ray.d.dot(u), ray.d.dot(v), ray.d.dot(d));
+
+        let mut best: Option<(f32, u8)> = None; // (t, 0 = cuerpo, 1 = tapa de abajo, 2 = de arriba)
+        let mut consider = |t: f32, part: u8| {
+            if t > tmin && t < tmax && best.is_none_or(|(bt, _)| t < bt) { best = Some((t, part)); }
+        };
+
+        // cuerpo: (ox + t·dx)² + (oy + t·dy)² = r²
+        let qa = dx * dx + dy * dy;
+        if qa > 1e-12 {
+            let qb = ox * dx + oy * dy;
+            let qc = ox * ox + oy * oy - r * r;
+            let disc = qb * qb - qa * qc;
+            if disc >= 0.0 {
+                let sq = disc.sqrt();
+                for t in [(-qb - sq) / qa, (-qb + sq) / qa] {
+                    let z = oz + t * dz;
+                    if (0.0..=h).contains(&z) { consider(t, 0); }
+                }
+            }
+        }
+        // tapas
+        if dz.abs() > 1e-12 {
+            for (zc, part) in [(0.0, 1), (h, 2)] {
+                let t = (zc - oz) / dz;
+                let (x, y) = (ox + t * dx, oy + t * dy);
+                if x * x + y * y <= r * r { consider(t, part); }
+            }
+        }
+
+        let (t, part) = best?;
+        let (x, y, z) = (ox + t * dx, oy + t * dy, oz + t * dz);
+        let (n, uv) = match part {
+            0 => ((u * x + v * y) / r, [0.5 + y.atan2(x) / (2.0 * PI), z / h]),
+            1 => (-d, [0.5 + x / (2.0 * r), 0.5 + y / (2.0 * r)]),
+            _ => (d, [0.5 + x / (2.0 * r), 0.5 + y / (2.0 * r)]),
+        };
+        Some(Hit { t, p: ray.at(t), n, mat_id: self.mat_id, uv })
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let (d, ..) = self.frame();
+        // cuánto se abre el disco de la tapa en cada eje
+        let r = self.radius;
+        let e = Vec3::new(r * (1.0 - d.x * d.x).max(0.0).sqrt(), r * (1.0 - d.y * d.y).max(0.0).sqrt(), r * (1.0 - d.z * d.z).max(0.0).sqrt());
+        let (a, b) = (self.a, self.b);
+        let min = Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)) - e;
+        let max = Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)) + e;
+        Some(Aabb { min, max, mat_id: self.mat_id })
+    }
+
+    fn mat_id(&self) -> usize { self.mat_id }
+
+    fn area(&self) -> f32 {
+        let h = (self.b - self.a).len();
+        2.0 * PI * self.radius * (h + self.radius)
+    }
+
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3) {
+        let (d, h, bu, bv) = self.frame();
+        let r = self.radius;
+        let side = h / (h + r); // fracción del área que es cuerpo
+        if u.x < side {
+            let phi = 2.0 * PI * u.y;
+            let n = bu * phi.cos() + bv * phi.sin();
+            (self.a + d * (u.z * h) + n * r, n)
+        } else {
+            // tapa: punto uniforme en el disco
+            let top = (u.x - side) / (1.0 - side) >= 0.5;
+            let (rr, phi) = (r * u.y.sqrt(), 2.0 * PI * u.z);
+            let base = if top { self.b } else { self.a };
+            (base + bu * (rr * phi.cos()) + bv * (rr * phi.sin()), if top { d } else { -d })
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// src/heightfield.rs
+// Terreno de alturas: una grilla de n × n muestras sobre un rectángulo,
+// cada celda partida en dos triángulos con normales por vértice. Para no
+// probar celda por celda, hay una pirámide de máximos (mip-max): el nivel l
+// guarda la altura máxima de cada bloque de 2^l × 2^l celdas, y el rayo
+// solo baja a los bloques cuya caja (hasta ese máximo) llega a cruzar.
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::shape::Shape;
+use crate::noise::{fbm2, value2};
+
+// Muestras por lado de un terreno de ruido, como mucho: 2049² vértices
+// con sus normales y la pirámide ya son unos 70 MB
+pub const MAX_RES: usize = 2049;
+
+// De dónde salen las alturas, para poder volver a escribirlo
+#[derive(Clone)]
+pub enum HeightSource {
+    Noise { seed: u32, res: usize, freq: f32 },
+    Image(String),
+}
+
+// Un material que reemplaza al de base a partir de cierta pendiente
+// (grados) o altura (sobre la base), con una franja de mezcla de `lo` a `hi`
+#[derive(Copy, Clone)]
+pub struct Layer { pub mat_id: usize, pub lo: f32, pub hi: f32 }
+
+pub struct Heightfield {
+    pub origin: Vec3,    // esquina de x y z mínimos, a la altura de la base
+    pub size: [f32; 2],  // en x y en z
+    pub height: f32,     // cuánto sube un 1 del mapa
+    pub source: HeightSource,
+    pub valley: Option<[f32; 4]>, // centro x z y radios: plano hasta el primero, entero desde el segundo
+    pub mat_id: usize,
+    pub slope: Option<Layer>,
+    pub high: Option<Layer>,
+    n: usize,
+    h: Vec<f32>,         // alturas en el mundo, fila por fila en z
+    normals: Vec<Vec3>,
+    mips: Vec<Vec<f32>>, // mips[l]: máximos de bloques de 2^l celdas de lado
+    ymin: f32,
+}
+
+fn smoothstep(lo: f32, hi: f32, x: f32) -> f32 {
+    let t = ((x - lo) / (hi - lo).max(1e-6)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl Heightfield {
+    // `map` da la altura en [0,1] de la muestra (i, j) de una grilla de n × n
+    fn new(origin: Vec3, size: [f32; 2], height: f32, n: usize, map: impl Fn(usize, usize) -> f32) -> Self {
+        let mut h = Vec::with_capacity(n * n);
+        for j in 0..n {
+            for i in 0..n { h.push(origin.y + height * map(i, j)); }
+        }
+        let mut f = Self {
+            origin, size, height, source: HeightSource::Noise { seed: 0, res: n, freq: 0.0 }, valley: None,
+            mat_id: 0, slope: None, high: None, n, h, normals: Vec::new(), mips: Vec::new(), ymin: origin.y,
+        };
+        f.finish();
+        f
+    }
+
+    // Las alturas de ruido fbm; `freq` en ciclos por unidad del mundo
+    pub fn noise(origin: Vec3, size: [f32; 2], height: f32, seed: u32, res: usize, freq: f32) -> Self {
+        let (dx, dz) = (size[0] / (res - 1) as f32, size[1] / (res - 1) as f32);
+        let mut f = Self::new(origin, size, height, res, |i, j| {
+            fbm2((origin.x + i as f32 * dx) * freq, (origin.z + j as f32 * dz) * freq, seed, 5)
+        });
+        f.source = HeightSource::Noise { seed, res, freq };
+        f
+    }
+
+    // Las alturas del gris de una imagen, un píxel por muestra; tiene que ser cuadrada
+    pub fn image(origin: Vec3, size: [f32; 2], height: f32, path: String, img: &crate::image::Pixels) -> Self {
+        let mut f = Self::new(origin, size, height, img.w, |i, j| img.gray(i, j));
+        f.source = HeightSource::Image(path);
+        f
+    }
+
+    // Aplana un círculo: la altura sobre la base se multiplica por 0 hasta
+    // el radio r0 y sube suave hasta 1 en r1
+    pub fn set_valley(&mut self, v: [f32; 4]) {
+        let [cx, cz, r0, r1] = v;
+        let (dx, dz) = self.spacing();
+        for j in 0..self.n {
+            for i in 0..self.n {
+                let (x, z) = (self.origin.x + i as f32 * dx - cx, self.origin.z + j as f32 * dz - cz);
+                let k = j * self.n + i;
+                self.h[k] = self.origin.y + (self.h[k] - self.origin.y) * smoothstep(r0, r1, (x * x + z * z).sqrt());
+            }
+        }
+        self.valley = Some(v);
+        self.finish();
+    }
+
+    fn spacing(&self) -> (f32, f32) {
+        (self.size[0] / (self.n - 1) as f32, self.size[1] / (self.n - 1) as f32)
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vec3 {
+        let (dx, dz) = self.spacing();
+        Vec3::new(self.origin.x + i as f32 * dx, self.h[j * self.n + i], self.origin.z + j as f32 * dz)
+    }
+
+    // Normales por diferencias centrales y la pirámide de máximos
+    fn finish(&mut self) {
+        let n = self.n;
+        let (dx, dz) = self.spacing();
+        let at = |i: usize, j: usize| self.h[j * n + i];
+        self.normals = (0..n * n).map(|k| {
+            let (i, j) = (k % n, k / n);
+            let (i0, i1, j0, j1) = (i.saturating_sub(1), (i + 1).min(n - 1), j.saturating_sub(1), (j + 1).min(n - 1));
+            let sx = (at(i1, j) - at(i0, j)) / ((i1 - i0) as f32 * dx);
+            let sz = (at(i, j1) - at(i, j0)) / ((j1 - j0) as f32 * dz);
+            Vec3::new(-sx, 1.0, -sz).norm()
+        }).collect();
+
+        // nivel 0: el máximo de las cuatro esquinas de cada celda
+        let c = n - 1;
+        let mut level: Vec<f32> = (0..c * c).map(|k| {
+            let (i, j) = (k % c, k / c);
+            at(i, j).max(at(i + 1, j)).max(at(i, j + 1)).max(at(i + 1, j + 1))
+        }).collect();
+        let mut side = c;
+        self.mips = Vec::new();
+        loop {
+            let next = side.div_ceil(2);
+            let prev = &level;
+            let up: Vec<f32> = (0..next * next).map(|k| {
+                let (i, j) = (2 * (k % next), 2 * (k / next));
+                let get = |a: usize, b: usize| if a < side && b < side { prev[b * side + a] } else { f32::NEG_INFINITY };
+                get(i, j).max(get(i + 1, j)).max(get(i, j + 1)).max(get(i + 1, j + 1))
+            }).collect();
+            self.mips.push(std::mem::replace(&mut level, up));
+            if side == 1 { break; }
+            side = next;
+        }
+        self.ymin = self.h.iter().copied().fold(f32::INFINITY, f32::min);
+    }
+
+    // Los dos triángulos de la celda (i, j), con la normal interpolada
+    fn cell_hit(&self, ray: &Ray, i: usize, j: usize, tmin: f32, tmax: f32) -> Option<(f32, Vec3)> {
+        let n = self.n;
+        let idx = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        let p = idx.map(|(a, b)| self.vertex(a, b));
+        let nv = idx.map(|(a, b)| self.normals[b * n + a]);
+        let mut best: Option<(f32, Vec3)> = None;
+        for [a, b, c] in [[0, 2, 1], [0, 3, 2]] {
+            // Möller-Trumbore, sin importar de qué lado se llega
+            let (e1, e2) = (p[b] - p[a], p[c] - p[a]);
+            let pv = ray.d.cross(e2);
+            let det = e1.dot(pv);
+            if det.abs() < 1e-12 { continue; }
+            let inv = 1.0 / det;
+            let s = ray.o - p[a];
+            let u = s.dot(pv) * inv;
+            if !(0.0..=1.0).contains(&u) { continue; }
+            let q = s.cross(e1);
+            let v = ray.d.dot(q) * inv;
+            if v < 0.0 || u + v > 1.0 { continue; }
+            let t = e2.dot(q) * inv;
+            if t <= tmin || t >= tmax || best.is_some_and(|(bt, _)| t >= bt) { continue; }
+            best = Some((t, (nv[a] * (1.0 - u - v) + nv[b] * u + nv[c] * v).norm()));
+        }
+        best
+    }
+
+    // El material del punto: el de base, o una capa con la mezcla punteada
+    // por ruido para que el borde no sea una línea
+    fn material(&self, p: Vec3, n: Vec3) -> usize {
+        let dither = value2(p.x * 7.0, p.z * 7.0, 0x5EED);
+        let mut m = self.mat_id;
+        if let Some(l) = self.high && smoothstep(l.lo, l.hi, p.y - self.origin.y) > dither { m = l.mat_id; }
+        let slope = n.y.clamp(-1.0, 1.0).acos().to_degrees();
+        if let Some(l) = self.slope && smoothstep(l.lo, l.hi, slope) > dither { m = l.mat_id; }
+        m
+    }
+}
+
+// Entrada y salida del rayo a una caja, dentro de (tmin, tmax)
+fn slab(ray: &Ray, inv: Vec3, min: Vec3, max: Vec3, tmin: f32, tmax: f32) -> Option<(f32, f32)> {
+    let (mut t0, mut t1) = (tmin, tmax);
+    for (o, i, lo, hi) in [(ray.o.x, inv.x, min.x, max.x), (ray.o.y, inv.y, min.y, max.y), (ray.o.z, inv.z, min.z, max.z)] {
+        // paralelo al eje: con el origen justo en el borde, 0 · ∞ daría NaN
+        if i.is_infinite() {
+            if o < lo || o > hi { return None; }
+            continue;
+        }
+        let (a, b) = ((lo - o) * i, (hi - o) * i);
+        t0 = t0.max(a.min(b));
+        t1 = t1.min(a.max(b));
+    }
+    (t0 <= t1).then_some((t0, t1))
+}
+
+impl Shape for Heightfield {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        let inv = Vec3::new(1.0 / ray.d.x, 1.0 / ray.d.y, 1.0 / ray.d.z);
+        let (dx, dz) = self.spacing();
+        let c = self.n - 1;
+        // bloques por revisar: (nivel, i, j); los hijos se apilan del más
+        // lejano al más cercano para ver primero lo que está adelante
+        let top = self.mips.len() - 1;
+        let mut stack = vec![(top, 0usize, 0usize)];
+        let mut best: Option<(f32, Vec3)> = None;
+        while let Some((l, i, j)) = stack.pop() {
+            let far = best.map_or(tmax, |(t, _)| t);
+            let cells = 1 << l;
+            let (i0, j0) = (i * cells, j * cells);
+            let (i1, j1) = ((i0 + cells).min(c), (j0 + cells).min(c));
+            let min = Vec3::new(self.origin.x + i0 as f32 * dx, self.ymin, self.origin.z + j0 as f32 * dz);
+            let max = Vec3::new(self.origin.x + i1 as f32 * dx, self.mips[l][j * c.div_ceil(cells) + i], self.origin.z + j1 as f32 * dz);
+            if slab(ray, inv, min, max, tmin, far).is_none() { continue; }
+            if l == 0 {
+                if let Some(h) = self.cell_hit(ray, i, j, tmin, far) { best = Some(h); }
+                continue;
+            }
+            let side = c.div_ceil(cells >> 1);
+            let mut kids = [(0, 0); 4];
+            let (fx, fz) = ((ray.d.x < 0.0) as usize, (ray.d.z < 0.0) as usize);
+            // orden de cercanía: la esquina por la que entra el rayo primero, la opuesta al final
+            for (k, (a, b)) in [(fx, fz), (1 - fx, fz), (fx, 1 - fz), (1 - fx, 1 - fz)].into_iter().enumerate() {
+                kids[k] = (2 * i + a, 2 * j + b);
+            }
+            for &(a, b) in kids.iter().rev() {
+                if a < side && b < side { stack.push((l - 1, a, b)); }
+            }
+        }
+        let (t, n) = best?;
+        let p = ray.at(t);
+        let uv = [(p.x - self.origin.x) / self.size[0], (p.z - self.origin.z) / self.size[1]];
+        Some(Hit { t, p, n, mat_id: self.material(p, n), uv })
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let top = self.mips.last().map_or(self.origin.y, |m| m[0]);
+        let max = Vec3::new(self.origin.x + self.size[0], top, self.origin.z + self.size[1]);
+        Some(Aabb { min: Vec3::new(self.origin.x, self.ymin, self.origin.z), max, mat_id: self.mat_id })
+    }
+
+    fn mat_id(&self) -> usize { self.mat_id }
+
+    // Como las instancias y los bloques: no se muestrea como luz
+    fn area(&self) -> f32 { 0.0 }
+
+    fn sample_surface(&self, _u: Vec3) -> (Vec3, Vec3) { (self.origin, Vec3::new(0.0, 1.0, 0.0)) }
+}
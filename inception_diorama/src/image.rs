@@ -0,0 +1,104 @@
+// src/image.rs
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// fb en formato 0xAARRGGBB (el mismo que recibe la ventana), ya en sRGB
+fn to_rgb8(fb: &[u32]) -> Vec<u8> {
+    fb.iter().flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8]).collect()
+}
+
+pub fn write_ppm(path: &Path, w: usize, h: usize, fb: &[u32]) -> io::Result<()> {
+    let mut f = BufWriter::new(File::create(path)?);
+    write!(f, "P6\n{w} {h}\n255\n")?;
+    f.write_all(&to_rgb8(fb))?;
+    f.flush()
+}
+
+pub fn write_png(path: &Path, w: usize, h: usize, fb: &[u32]) -> io::Result<()> {
+    let f = BufWriter::new(File::create(path)?);
+    let mut enc = png::Encoder::new(f, w as u32, h as u32);
+    enc.set_color(png::ColorType::Rgb);
+    enc.set_depth(png::BitDepth::Eight);
+    enc.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut wr = enc.write_header().map_err(io::Error::other)?;
+    wr.write_image_data(&to_rgb8(fb)).map_err(io::Error::other)
+}
+
+// Elige el formato por la extensión
+pub fn save(path: &Path, w: usize, h: usize, fb: &[u32]) -> io::Result<()> {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+        Some("png") => write_png(path, w, h, fb),
+        Some("ppm") => write_ppm(path, w, h, fb),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "extensión no soportada (usar .png o .ppm)")),
+    }
+}
+
+// Una imagen leída: `channels` valores por píxel, de arriba hacia abajo,
+// en [0,1] y tal como vienen (sin sacar la gamma)
+pub struct Pixels { pub w: usize, pub h: usize, pub channels: usize, pub data: Vec<f32> }
+
+impl Pixels {
+    // Gris del píxel: el promedio de r, g y b, o el único canal
+    pub fn gray(&self, x: usize, y: usize) -> f32 {
+        let i = (y * self.w + x) * self.channels;
+        if self.channels >= 3 { (self.data[i] + self.data[i + 1] + self.data[i + 2]) / 3.0 } else { self.data[i] }
+    }
+}
+
+// PNG (gris, rgb o rgba, 8 o 16 bits) o PPM/PGM binario, por la extensión
+pub fn load(path: &Path) -> io::Result<Pixels> {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+        Some("png") => read_png(path),
+        Some("ppm" | "pgm") => read_pnm(&std::fs::read(path)?),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "extensión no soportada (usar .png, .ppm o .pgm)")),
+    }
+}
+
+fn read_png(path: &Path) -> io::Result<Pixels> {
+    let mut dec = png::Decoder::new(File::open(path)?);
+    // paletas y grises de menos de 8 bits, a 8 bits por canal
+    dec.set_transformations(png::Transformations::EXPAND);
+    let mut rd = dec.read_info().map_err(io::Error::other)?;
+    let mut buf = vec![0; rd.output_buffer_size()];
+    let info = rd.next_frame(&mut buf).map_err(io::Error::other)?;
+    let channels = info.color_type.samples();
+    let data = match info.bit_depth {
+        png::BitDepth::Sixteen => buf[..info.buffer_size()].chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]]) as f32 / 65535.0).collect(),
+        _ => buf[..info.buffer_size()].iter().map(|&b| b as f32 / 255.0).collect(),
+    };
+    Ok(Pixels { w: info.width as usize, h: info.height as usize, channels, data })
+}
+
+// P5 (gris) o P6 (rgb): encabezado en texto, con comentarios, y los datos en binario
+fn read_pnm(bytes: &[u8]) -> io::Result<Pixels> {
+    let bad = |m: &str| io::Error::new(io::ErrorKind::InvalidData, m.to_string());
+    let mut pos = 0;
+    let mut field = || -> io::Result<&[u8]> {
+        loop {
+            while pos < bytes.len() && bytes[pos].is_ascii_whitespace() { pos += 1; }
+            if bytes.get(pos) != Some(&b'#') { break; }
+            while pos < bytes.len() && bytes[pos] != b'\n' { pos += 1; }
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() { pos += 1; }
+        if start == pos { return Err(bad("encabezado cortado")); }
+        Ok(&bytes[start..pos])
+    };
+    let channels = match field()? { b"P5" => 1, b"P6" => 3, _ => return Err(bad("solo PGM (P5) o PPM (P6) binarios")) };
+    let mut num = || -> io::Result<usize> {
+        std::str::from_utf8(field()?).ok().and_then(|s| s.parse().ok()).ok_or_else(|| bad("número inválido en el encabezado"))
+    };
+    let (w, h, max) = (num()?, num()?, num()?);
+    if max == 0 || max > 65535 { return Err(bad("valor máximo inválido")); }
+    // un solo blanco separa el encabezado de los datos
+    let body = bytes.get(pos + 1..).unwrap_or(&[]);
+    let n = w * h * channels;
+    let data: Vec<f32> = if max < 256 {
+        body.iter().take(n).map(|&b| b as f32 / max as f32).collect()
+    } else {
+        body.chunks_exact(2).take(n).map(|b| u16::from_be_bytes([b[0], b[1]]) as f32 / max as f32).collect()
+    };
+    if data.len() < n { return Err(bad("faltan datos")); }
+    Ok(Pixels { w, h, channels, data })
+}
@@ -0,0 +1,91 @@
+// src/instance.rs
+// Prototipos e instancias: un grupo de objetos se define una vez, con su
+// propio BVH, y se coloca muchas veces con una transformación. El rayo se
+// lleva al espacio del prototipo y la normal se vuelve a llevar al mundo.
+use std::sync::Arc;
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::bvh::Bvh;
+use crate::shape::{Object, Shape};
+use crate::transform::Transform;
+
+pub struct Prototype {
+    pub name: String,
+    pub objects: Vec<Object>, // en coordenadas locales
+    bvh: Bvh,
+    bounds: Option<Aabb>, // None si tiene algo infinito
+}
+
+impl Prototype {
+    pub fn new(name: String, objects: Vec<Object>) -> Self {
+        let bvh = Bvh::build(&objects);
+        let mut bounds = objects.first().and_then(|o| o.bounds());
+        for o in &objects {
+            bounds = match (bounds, o.bounds()) {
+                (Some(a), Some(b)) => Some(Aabb { min: vmin(a.min, b.min), max: vmax(a.max, b.max), mat_id: a.mat_id }),
+                _ => None,
+            };
+        }
+        Self { name, objects, bvh, bounds }
+    }
+}
+
+// Una copia colocada: solo guarda la transformación y comparte el prototipo
+#[derive(Clone)]
+pub struct Instance {
+    pub proto: Arc<Prototype>,
+    pub placement: (Vec3, Vec3, Vec3), // posición, rotación (grados), escala
+    xf: Transform,
+    inv: Transform,
+}
+
+impl Instance {
+    pub fn new(proto: Arc<Prototype>, pos: Vec3, rot: Vec3, scale: Vec3) -> Self {
+        let xf = Transform::new(pos, rot, scale);
+        Self { proto, placement: (pos, rot, scale), xf, inv: xf.inverse() }
+    }
+}
+
+impl Shape for Instance {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        // sin normalizar la dirección, la t es la misma en los dos espacios
+        let local = Ray { o: self.inv.point(ray.o), d: self.inv.vector(ray.d), cone: ray.cone };
+        let mut h = self.proto.bvh.closest(&self.proto.objects, &local, tmin, tmax)?;
+        h.p = ray.at(h.t);
+        h.n = self.xf.normal(h.n);
+        Some(h)
+    }
+
+    // Las 8 esquinas de la caja local, transformadas
+    fn bounds(&self) -> Option<Aabb> {
+        let b = self.proto.bounds?;
+        let mut min = Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut max = -min;
+        for k in 0..8 {
+            let c = Vec3::new(
+                if k & 1 == 0 { b.min.x } else { b.max.x },
+                if k & 2 == 0 { b.min.y } else { b.max.y },
+                if k & 4 == 0 { b.min.z } else { b.max.z },
+            );
+            let p = self.xf.point(c);
+            min = vmin(min, p);
+            max = vmax(max, p);
+        }
+        Some(Aabb { min, max, mat_id: b.mat_id })
+    }
+
+    fn mat_id(&self) -> usize { self.proto.objects[0].mat_id() }
+
+    // Las instancias no se muestrean como luces: con escala no uniforme el
+    // área no se conserva. Si tienen partes emisivas, se ven igual al pegarles.
+    fn area(&self) -> f32 { 0.0 }
+
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3) {
+        let (p, n) = self.proto.objects[0].sample_surface(u);
+        (self.xf.point(p), self.xf.normal(n))
+    }
+}
+
+fn vmin(a: Vec3, b: Vec3) -> Vec3 { Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)) }
+fn vmax(a: Vec3, b: Vec3) -> Vec3 { Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)) }
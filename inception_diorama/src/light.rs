@@ -0,0 +1,86 @@
+// src/light.rs
+use crate::math::Vec3;
+
+// Las intensidades se expresan como irradiancia sobre una superficie
+// perpendicular (igual que el sol de siempre: difuso = albedo · E · cos),
+// salvo la de área, que es radiancia del rectángulo.
+#[derive(Copy, Clone)]
+pub enum Light {
+    // Luz en el infinito; `dir` es hacia dónde viaja la luz
+    Directional { dir: Vec3, color: Vec3, intensity: f32 },
+    // Caída con 1/d², recortada suavemente a cero en `range` (0 = sin recorte)
+    Point { pos: Vec3, color: Vec3, intensity: f32, range: f32 },
+    // Como Point, limitada a un cono; ángulos en grados desde el eje
+    Spot { pos: Vec3, dir: Vec3, color: Vec3, intensity: f32, range: f32, inner: f32, outer: f32 },
+    // Rectángulo corner + s·u + t·v que emite del lado de u×v;
+    // `samples` por eje para las sombras suaves
+    Area { corner: Vec3, u: Vec3, v: Vec3, color: Vec3, intensity: f32, samples: u32 },
+}
+
+pub struct LightSample {
+    pub wi: Vec3,   // hacia la luz
+    pub dist: f32,  // hasta la luz (infinito para direccional)
+    pub e: Vec3,    // irradiancia que llega, antes del coseno en la superficie
+}
+
+// Ventana suave para que la luz puntual llegue a 0 en `range`
+fn range_window(dist: f32, range: f32) -> f32 {
+    if range <= 0.0 { return 1.0; }
+    let x = (dist / range).powi(4);
+    (1.0 - x).max(0.0).powi(2)
+}
+
+fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl Light {
+    // Muestras por punto de sombreado (las de área, en una grilla n×n)
+    pub fn samples(&self) -> u32 {
+        match *self { Light::Area { samples, .. } => samples.max(1) * samples.max(1), _ => 1 }
+    }
+
+    // Muestra de la luz vista desde `p`; (su, sv) en [0,1)² elige el
+    // punto sobre las luces de área. None si no ilumina a `p`.
+    pub fn sample(&self, p: Vec3, su: f32, sv: f32) -> Option<LightSample> {
+        match *self {
+            Light::Directional { dir, color, intensity } => {
+                Some(LightSample { wi: -dir.norm(), dist: f32::INFINITY, e: color * intensity })
+            }
+            Light::Point { pos, color, intensity, range } => {
+                let to = pos - p;
+                let d2 = to.dot(to).max(1e-6);
+                let dist = d2.sqrt();
+                let att = range_window(dist, range) / d2;
+                if att <= 0.0 { return None; }
+                Some(LightSample { wi: to / dist, dist, e: color * (intensity * att) })
+            }
+            Light::Spot { pos, dir, color, intensity, range, inner, outer } => {
+                let to = pos - p;
+                let d2 = to.dot(to).max(1e-6);
+                let dist = d2.sqrt();
+                let wi = to / dist;
+                let cone = smoothstep(outer.to_radians().cos(), inner.to_radians().cos(), (-wi).dot(dir.norm()));
+                let att = cone * range_window(dist, range) / d2;
+                if att <= 0.0 { return None; }
+                Some(LightSample { wi, dist, e: color * (intensity * att) })
+            }
+            Light::Area { corner, u, v: vv, color, intensity, .. } => {
+                let q = corner + u * su + vv * sv;
+                let nl = u.cross(vv);
+                let area = nl.len();
+                let to = q - p;
+                let d2 = to.dot(to).max(1e-6);
+                let dist = d2.sqrt();
+                let wi = to / dist;
+                let cos_l = (-wi).dot(nl / area.max(1e-8));
+                if cos_l <= 0.0 { return None; }
+                // radiancia · ángulo sólido del rectángulo visto desde p
+                Some(LightSample { wi, dist, e: color * (intensity * cos_l * area / d2) })
+            }
+        }
+    }
+
+    pub fn is_directional(&self) -> bool { matches!(self, Light::Directional { .. }) }
+}
@@ -1,38 +1,142 @@
 // src/main.rs
 mod math;     mod ray;     mod camera;
 mod aabb;     mod material; mod scene;
+mod bvh;      mod render;   mod cli;
+mod image;    mod scene_file; mod watch;
+mod overlay;  mod rng;      mod pathtrace;
+mod light;    mod sky;      mod tonemap;
+mod shape;    mod sphere;   mod plane;
+mod cylinder; mod triangle; mod mesh;
+mod obj;      mod transform; mod instance;
+mod obox;     mod noise;    mod voxel;
+mod vox;      mod sdf;      mod csg;
+mod heightfield; mod ggx; mod texture;
 
-use minifb::{Key, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use camera::{Camera, CameraPreset};
+use render::{Renderer, Mode, Accum};
+use cli::Options;
+use scene::Scene;
+use tonemap::Display;
 use math::Vec3;
-use camera::Camera;
 
 fn main() {
-    let mut w: usize = 640;
-    let mut h: usize = 360;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    let opts = Options::parse(&args).unwrap_or_else(|e| {
+        eprintln!("{e}\n{}", cli::USAGE);
+        std::process::exit(2);
+    });
+    let mut renderer = Renderer::new(opts.threads);
+    renderer.spp = opts.spp;
+    renderer.max_bounces = opts.bounces;
+    if opts.path { renderer.mode = Mode::PathTrace; }
+
+    let mut scene = match &opts.scene {
+        Some(path) => scene_file::load(Path::new(path)).unwrap_or_else(|e| {
+            eprintln!("{path}: {e}");
+            std::process::exit(1);
+        }),
+        None => scene::Scene::test_scene(),
+    };
+    for m in &scene.merged {
+        let name = m.grid.source.as_ref().map_or("voxels", |(path, _)| path.as_str());
+        eprintln!("{name}: {} bloques → {} cajas", m.blocks, m.objects.len());
+    }
+    if let Some(t) = opts.time {
+        scene.sky.daylight.get_or_insert_with(Default::default);
+        scene.sky.set_time(t);
+    }
+    let pose = opts.camera_pose(&scene.cameras).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(2);
+    });
+
+    if let Some(path) = &opts.save_scene {
+        if let Err(e) = scene_file::save(&scene, Path::new(path)) {
+            eprintln!("{path}: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if opts.headless {
+        if let Err(e) = headless(&opts, &renderer, &scene, &pose) {
+            eprintln!("{}: {e}", opts.out);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut w: usize = opts.width;
+    let mut h: usize = opts.height;
     let mut window = Window::new(
-        "Diorama (modo fluido) — Flechas: yaw/pitch | Z/X: roll | Q/E: dolly",
+        "Diorama (modo fluido) — Flechas: yaw/pitch | Z/X: roll | Q/E: dolly | P: path tracing | [ ]: hora | T, -/=: tonos y exposición",
         w, h, WindowOptions { resize: true, scale: minifb::Scale::X1, ..WindowOptions::default() }
     ).unwrap();
 
     let mut fb = vec![0u32; w*h];
+    let mut hdr = vec![Vec3::default(); w*h];
+    let mut display = Display { tonemap: opts.tonemap, exposure: opts.exposure };
 
     // Cámara que gira en su eje
-    let mut eye = Vec3::new(0.0, 0.0, 12.0);
-    let mut yaw:f32 = 0.0;
-    let mut pitch:f32 = 0.0;
-    let mut roll:f32 = 0.0;
+    let mut eye = pose.eye;
+    let mut yaw:f32 = pose.yaw;
+    let mut pitch:f32 = pose.pitch;
+    let mut roll:f32 = pose.roll;
     let rot_step = 1.5;
     let dolly = 0.35;
 
-    let scene = scene::Scene::test_scene();
-    let mut fov = 60.0_f32;
+    let fov = pose.fov;
+
+    // Recarga en caliente si la escena vino de un archivo
+    let mut watcher = opts.scene.as_ref().map(watch::SceneWatcher::new);
+    let mut reload_error: Option<String> = None;
+
+    // Path tracing progresivo: se acumula mientras la cámara está quieta
+    let mut accum = Accum::new(w, h);
+    let mut last_view = [0.0f32; 9];
+
+    // Hora del día con [ y ]
+    let time_rate = 2.0; // horas por segundo
+    let mut last_frame = Instant::now();
+
+    // Aviso abajo a la izquierda que se va solo a los 2 s
+    let mut status = String::new();
+    let mut status_until = Instant::now();
 
     while window.is_open() {
+        if let Some(res) = watcher.as_mut().and_then(|w| w.poll()) {
+            match res {
+                Ok(mut s) => {
+                    // sigue la hora a la que se había llegado, no la del archivo
+                    // (el cielo de `--time` también sigue aunque el archivo no lo tenga)
+                    if let Some(t) = scene.sky.time() {
+                        if opts.time.is_some() { s.sky.daylight.get_or_insert_with(Default::default); }
+                        s.sky.set_time(t);
+                    }
+                    scene = s;
+                    reload_error = None;
+                    accum.frames = 0;
+                }
+                Err(e) => {
+                    // se queda la escena anterior hasta que el archivo vuelva a estar bien
+                    let path = opts.scene.as_deref().unwrap_or_default();
+                    reload_error = Some(format!("{path}: {e}"));
+                }
+            }
+        }
         let (nw, nh) = window.get_size();
         if nw != w || nh != h {
             w = nw.max(1);
             h = nh.max(1);
             fb.resize(w * h, 0);
+            hdr.resize(w * h, Vec3::default());
         }   
         // Controles
         if window.is_key_down(Key::Left)  { yaw -= rot_step; }
@@ -41,6 +145,31 @@ fn main() {
         if window.is_key_down(Key::Down)  { pitch = (pitch - rot_step).clamp(-85.0,85.0); }
         if window.is_key_down(Key::Z)     { roll -= rot_step; }
         if window.is_key_down(Key::X)     { roll += rot_step; }
+        let dt = last_frame.elapsed().as_secs_f32().min(0.25);
+        last_frame = Instant::now();
+        if let Some(t) = scene.sky.time() {
+            let mut dir = 0.0;
+            if window.is_key_down(Key::LeftBracket)  { dir -= 1.0; }
+            if window.is_key_down(Key::RightBracket) { dir += 1.0; }
+            if dir != 0.0 {
+                scene.sky.set_time(t + dir * time_rate * dt);
+                let minutes = (scene.sky.time().unwrap_or(0.0) * 60.0) as u32;
+                status = format!("hora {:02}:{:02}", minutes / 60, minutes % 60);
+                status_until = Instant::now() + Duration::from_secs(2);
+            }
+        }
+        let mut display_changed = false;
+        if window.is_key_pressed(Key::T, KeyRepeat::No) { display.tonemap = display.tonemap.next(); display_changed = true; }
+        if window.is_key_pressed(Key::Minus, KeyRepeat::Yes) { display.exposure -= 0.5; display_changed = true; }
+        if window.is_key_pressed(Key::Equal, KeyRepeat::Yes) { display.exposure += 0.5; display_changed = true; }
+        if display_changed {
+            status = format!("{} {:+.1} EV", display.tonemap.name(), display.exposure);
+            status_until = Instant::now() + Duration::from_secs(2);
+        }
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            renderer.mode = if renderer.mode == Mode::Whitted { Mode::PathTrace } else { Mode::Whitted };
+            accum.frames = 0;
+        }
 
         let cam = Camera::from_euler(eye, yaw, pitch, roll, fov, w as f32 / h as f32);
 
@@ -50,23 +179,42 @@ fn main() {
         if window.is_key_down(Key::E) { eye = eye + forward * (dolly);  }
 
         // Render
-        let inv_w = 1.0 / w as f32;
-        let inv_h = 1.0 / h as f32;
-        for j in 0..h {
-            let y = ((j as f32)*inv_h)*2.0 - 1.0; // [-1,1]
-            for i in 0..w {
-                let x = ((i as f32)*inv_w)*2.0 - 1.0; // [-1,1]
-                let ray = cam.ray_for(x, -y); // y invertida para imagen
-                let col = scene.trace(&ray, 1).clamp01(); // 0/1 rebote máx
-                fb[j*w+i] = rgb_u32(col.x, col.y, col.z);
+        match renderer.mode {
+            Mode::Whitted => {
+                renderer.render(&scene, &cam, w, h, &mut hdr);
+                display.resolve(&hdr, 1.0, &mut fb);
+            }
+            Mode::PathTrace => {
+                let time = scene.sky.time().unwrap_or(0.0);
+                let view = [eye.x, eye.y, eye.z, yaw, pitch, roll, w as f32, h as f32, time];
+                if view != last_view || accum.frames == 0 {
+                    accum.reset(w, h);
+                    last_view = view;
+                }
+                renderer.accumulate(&scene, &cam, w, h, &mut accum);
+                accum.resolve(&display, &mut fb);
             }
         }
+        if Instant::now() < status_until {
+            overlay::draw_text(&mut fb, w, h, 8, h.saturating_sub(30), &status, 0xffff_f0c0);
+        }
+        if let Some(msg) = &reload_error {
+            overlay::draw_text(&mut fb, w, h, 8, 8, msg, 0xffff_5050);
+        }
 
         window.update_with_buffer(&fb, w, h).unwrap();
     }
 }
 
-fn rgb_u32(r:f32,g:f32,b:f32)->u32{
-    let (r,g,b)=((r*255.0) as u32, (g*255.0) as u32, (b*255.0) as u32);
-    (255<<24) | (r<<16) | (g<<8) | b
-}
\ No newline at end of file
+// Render sin ventana directo a archivo
+fn headless(opts: &Options, renderer: &Renderer, scene: &Scene, pose: &CameraPreset) -> std::io::Result<()> {
+    let (w, h) = (opts.width, opts.height);
+    let cam = Camera::from_euler(pose.eye, pose.yaw, pose.pitch, pose.roll, pose.fov, w as f32 / h as f32);
+    let mut hdr = vec![Vec3::default(); w*h];
+    let t0 = Instant::now();
+    renderer.render(scene, &cam, w, h, &mut hdr);
+    eprintln!("{w}x{h} @ {} spp en {:.2?} ({} hilos)", opts.spp, t0.elapsed(), renderer.threads);
+    let mut fb = vec![0u32; w*h];
+    Display { tonemap: opts.tonemap, exposure: opts.exposure }.resolve(&hdr, 1.0, &mut fb);
+    image::save(Path::new(&opts.out), w, h, &fb)
+}
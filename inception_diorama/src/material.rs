@@ -1,6 +1,10 @@
 // src/material.rs
 use crate::math::{Vec3, v, reflect, refract, fresnel_schlick, lerp};
 use crate::ray::Ray;
+use crate::aabb::Hit;
+use crate::scene::Scene;
+use crate::rng::Rng;
+use crate::ggx::Ggx;
 
 #[derive(Copy, Clone)]
 pub enum Kind {
@@ -11,6 +15,14 @@ pub enum Kind {
     Plastic { rough: f32 }, // difuso + specular
 }
 
+// Relieve sin geometría: mueve la normal antes de iluminar. Las texturas
+// son índices en `Scene::textures`.
+#[derive(Copy, Clone)]
+pub enum Relief {
+    Bump { tex: usize, height: f32 },     // alturas: el gris de la textura por `height`
+    Normal { tex: usize, strength: f32 }, // mapa de normales en el espacio tangente
+}
+
 #[derive(Copy, Clone)]
 pub struct Material {
     pub kind: Kind,
@@ -18,103 +30,117 @@ pub struct Material {
     pub specular: f32,
     pub transparency: f32,
     pub reflectivity: f32,
+    pub texture: Option<usize>, // en `Scene::textures`; da el albedo punto a punto
+    pub relief: Option<Relief>,
+}
+
+// Lo que queda de la luz tras recorrer `dist` dentro de un medio que
+// absorbe (Beer-Lambert); lo que no absorbe pasa entero aunque el tramo sea
+// infinito (un rayo que sale al cielo desde adentro)
+pub fn beer(absorption: Vec3, dist: f32) -> Vec3 {
+    let f = |a: f32| if a > 0.0 { (-a * dist).exp() } else { 1.0 };
+    Vec3::new(f(absorption.x), f(absorption.y), f(absorption.z))
 }
 
-pub fn shade(hit_p:Vec3, n:Vec3, ray:&Ray, mat:&Material, sky:&dyn Fn(Vec3)->Vec3, depth:i32)->Vec3 {
+// Reflejos borrosos de Whitted: direcciones de la GGX promediadas
+const GLOSSY_SAMPLES: u32 = 4;
+
+// Suma sobre las luces de (irradiancia · cos, brillo especular), con sombras;
+// `spec(wi)` da el brillo por unidad de irradiancia que llega desde wi.
+// El sol tapado conserva un 25%: en Whitted no hay luz de cielo que rellene.
+fn light_terms(scene:&Scene, h:&Hit, spec: impl Fn(Vec3)->Vec3)->(Vec3, Vec3) {
+    let mut rng = Rng::for_point(h.p);
+    let (mut diffuse, mut highlight) = (v(0.0), v(0.0));
+    scene.each_light(h.p, h.n, &mut rng, |light, s, visible| {
+        let shadow_factor = if visible { 1.0 } else if light.is_directional() { 0.25 } else { 0.0 };
+        if shadow_factor == 0.0 { return; }
+        let ndotl = h.n.dot(s.wi).max(0.0);
+        diffuse = diffuse + s.e * (ndotl * shadow_factor);
+        highlight = highlight + s.e * spec(s.wi) * shadow_factor;
+    });
+    (diffuse, highlight)
+}
+
+// Ambiente + luces difusas + brillo especular: la base de las superficies opacas
+fn direct(scene:&Scene, h:&Hit, ray:&Ray, mat:&Material, shininess:f32)->Vec3 {
+    let view_dir = -ray.d;
+    let (diffuse, highlight) = light_terms(scene, h, |wi| v(view_dir.dot(reflect(-wi, h.n)).max(0.0).powf(shininess)));
+    let ambient = mat.albedo * scene.ambient();
+    ambient + mat.albedo * diffuse + highlight * (mat.specular * 0.8)
+}
+
+// Reflejo del lóbulo GGX: unas pocas direcciones estratificadas, o una
+// sola si es casi un espejo o ya se está hondo en la recursión
+fn glossy(scene:&Scene, h:&Hit, ray:&Ray, lobe:&Ggx, depth:i32)->Vec3 {
+    if depth <= 1 { return v(0.0); }
+    let count = if lobe.alpha < 0.01 || depth <= 2 { 1 } else { GLOSSY_SAMPLES };
+    let mut rng = Rng::for_point(h.p);
+    let mut sum = v(0.0);
+    for k in 0..count {
+        let u1 = (k as f32 + rng.f32()) / count as f32;
+        let Some((d, w)) = lobe.sample(u1, rng.f32()) else { continue };
+        sum = sum + scene.trace(&Ray { o: h.p + h.n*1e-3, d, cone: ray.cone.after(h.t) }, depth - 1) * w;
+    }
+    sum / count as f32
+}
+
+pub fn shade(scene:&Scene, h:&Hit, ray:&Ray, mat:&Material, depth:i32)->Vec3 {
+    let (hit_p, n) = (h.p, h.n);
     let view = (-ray.d).norm();
-    let cos_theta = n.dot(view).max(0.0);
     let f0 = lerp(v(0.04), mat.albedo, mat.reflectivity.clamp(0.0,1.0));
-    let fres = fresnel_schlick(cos_theta, f0).clamp01();
 
     match mat.kind {
         Kind::Emissive { intensity } => mat.albedo * intensity,
         Kind::Diffuse => {
-            // lambert + un poco de fresnel especular fake
-            let base = mat.albedo * cos_theta;
-            let spec = v(1.0) * fres.x; // aprox
-            (base*0.9 + spec*0.1).clamp01()
+            let base = direct(scene, h, ray, mat, 64.0);
+
+            // Reflexión especular
+            let mut specular = v(0.0);
+            if mat.reflectivity > 0.01 && depth > 1 {
+                let reflect_ray = Ray { o: hit_p + n*1e-3, d: reflect(ray.d, n).norm(), cone: ray.cone.after(h.t) };
+                specular = scene.trace(&reflect_ray, depth - 1) * mat.reflectivity;
+            }
+            base + specular
         }
-        Kind::Metal { rough } => {
-            if depth <= 0 { return v(0.0); }
-            let refl_dir = reflect(ray.d, n).norm();
-            // “roughness” barato: mezcla con un poco la normal
-            let jitter = n * (rough*0.2);
-            let rd = (refl_dir + jitter).norm();
-            let rray = Ray { o: hit_p + n*1e-3, d: rd };
-            sky(rray.d) * fres.x
+        Kind::Metal { rough } | Kind::Plastic { rough } => {
+            // Cook-Torrance; en el plástico lo que no refleja la capa especular
+            // pasa al difuso de abajo, el metal no tiene
+            let lobe = Ggx::new(n, view, rough, f0);
+            let kd = match mat.kind {
+                Kind::Plastic { .. } => mat.albedo * (v(1.0) - lobe.albedo()),
+                _ => v(0.0),
+            };
+            let (diffuse, highlight) = light_terms(scene, h, |wi| lobe.eval(wi));
+            kd * (scene.ambient() + diffuse) + highlight + glossy(scene, h, ray, &lobe, depth)
         }
         Kind::Dielectric { ior, absorption } => {
-            if depth <= 0 { return v(0.0); }
             let entering = ray.d.dot(n) < 0.0;
             let (n1, n2, nn) = if entering {(1.0, ior, n)} else {(ior, 1.0, -n)};
             let eta = n1 / n2;
             let refr = refract(ray.d, nn, eta);
-            let refl_dir = reflect(ray.d, nn).norm();
-            let refl_col = sky(refl_dir);
+            // Fresnel con la normal del lado de donde viene el rayo, también al salir
+            let fres = fresnel_schlick(view.dot(nn).max(0.0), f0).clamp01();
+            // lo que viaja por adentro se atenúa con lo que recorre hasta lo próximo que toca
+            let inside = |(c, len): (Vec3, f32)| c * beer(absorption, len);
+            let refl_ray = Ray { o: hit_p + nn*1e-3, d: reflect(ray.d, nn).norm(), cone: ray.cone.after(h.t) };
+            let refl = scene.trace_len(&refl_ray, depth - 1);
+            let refl_col = if entering { refl.0 } else { inside(refl) };
 
-            match refr {
+            // la parte no transparente se ilumina como difuso
+            let opaque = if mat.transparency < 1.0 { direct(scene, h, ray, mat, 64.0) } else { v(0.0) };
+            let glass = match refr {
                 Some(td) => {
-                    let trans_ray = Ray { o: hit_p - nn*1e-3, d: td.norm() };
-                    // atenuación (Beer-Lambert)
-                    let dist = 1.0; // corto salto
-                    let att = Vec3::new(
-                        (-absorption.x*dist).exp(),
-                        (-absorption.y*dist).exp(),
-                        (-absorption.z*dist).exp()
-                    );
-                    let trans_col = sky(trans_ray.d) * att;
+                    let trans_ray = Ray { o: hit_p - nn*1e-3, d: td.norm(), cone: ray.cone.after(h.t) };
+                    let trans = scene.trace_len(&trans_ray, depth - 1);
+                    let trans_col = if entering { inside(trans) } else { trans.0 };
                     (refl_col * fres + trans_col * (v(1.0)-fres)) * mat.albedo
                 }
                 None => {
                     // reflexión total
                     refl_col * mat.albedo
                 }
-            }
-        }
-        Kind::Plastic { rough } => {
-            // difuso + specular Fresnel; “rough” solo atenúa el spec
-            let lambert = mat.albedo * cos_theta;
-            let spec = v(1.0) * fres * (1.0 - rough).max(0.0);
-            (lambert*0.85 + spec*0.15).clamp01()
+            };
+            lerp(opaque, glass, mat.transparency.clamp(0.0, 1.0))
         }
     }
 }
-pub fn default_materials() -> Vec<Material> {
-    vec![
-        Material { // 0 piso difuso gris
-            kind: Kind::Diffuse,
-            albedo: Vec3::new(0.8, 0.8, 0.85),
-            specular: 0.1,
-            transparency: 0.0,
-            reflectivity: 0.0,
-        },
-        Material { // 1 metal pulido
-            kind: Kind::Metal { rough: 0.2 },
-            albedo: Vec3::new(0.9, 0.9, 0.95),
-            specular: 1.0,
-            transparency: 0.0,
-            reflectivity: 0.9,
-        },
-        Material { // 2 vidrio
-            kind: Kind::Dielectric { ior: 1.5, absorption: Vec3::new(0.1, 0.03, 0.01) },
-            albedo: Vec3::new(1.0, 1.0, 1.0),
-            specular: 0.04,
-            transparency: 1.0,
-            reflectivity: 0.04,
-        },
-        Material { // 3 plástico rojo
-            kind: Kind::Plastic { rough: 0.4 },
-            albedo: Vec3::new(0.9, 0.2, 0.25),
-            specular: 0.2,
-            transparency: 0.0,
-            reflectivity: 0.04,
-        },
-        Material { // 4 emisivo blanco
-            kind: Kind::Emissive { intensity: 4.0 },
-            albedo: Vec3::new(0.9, 0.9, 1.0),
-            specular: 0.0,
-            transparency: 0.0,
-            reflectivity: 0.0,
-        },
-    ]
-}
\ No newline at end of file
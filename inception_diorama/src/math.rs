@@ -5,6 +5,7 @@ pub struct Vec3 { pub x: f32, pub y: f32, pub z: f32 }
 impl Vec3 {
     pub fn new(x:f32,y:f32,z:f32)->Self{Self{x,y,z}}
     pub fn dot(self, o:Self)->f32{ self.x*o.x + self.y*o.y + self.z*o.z }
+    pub fn cross(self, o:Self)->Self{ Self::new(self.y*o.z - self.z*o.y, self.z*o.x - self.x*o.z, self.x*o.y - self.y*o.x) }
     pub fn len(self)->f32{ self.dot(self).sqrt() }
     pub fn norm(self)->Self{ let l=self.len().max(1e-8); Self::new(self.x/l,self.y/l,self.z/l)}
     pub fn clamp01(self)->Self{ Self::new(self.x.clamp(0.0,1.0), self.y.clamp(0.0,1.0), self.z.clamp(0.0,1.0)) }
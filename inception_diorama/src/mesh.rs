@@ -0,0 +1,90 @@
+// src/mesh.rs
+// Malla de triángulos con su propio BVH. Para la escena es un objeto más:
+// el BVH de la escena encuentra la caja de la malla y este, el triángulo.
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::bvh::Bvh;
+use crate::shape::Shape;
+use crate::triangle::Triangle;
+
+// Triángulo con normales y uv por vértice (si el archivo las trae)
+#[derive(Copy, Clone)]
+pub struct MeshTri {
+    pub tri: Triangle,
+    pub normals: Option<[Vec3; 3]>,
+    pub uvs: Option<[[f32; 2]; 3]>,
+}
+
+impl Shape for MeshTri {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        let mut h = self.tri.hit(ray, tmin, tmax)?;
+        let [u, w] = h.uv;
+        let k = 1.0 - u - w;
+        if let Some([n0, n1, n2]) = self.normals {
+            let n = (n0 * k + n1 * u + n2 * w).norm();
+            // la normal suave del lado de la geométrica, para no dar vuelta la cara
+            h.n = if n.dot(h.n) < 0.0 { -n } else { n };
+        }
+        if let Some([a, b, c]) = self.uvs {
+            h.uv = [a[0] * k + b[0] * u + c[0] * w, a[1] * k + b[1] * u + c[1] * w];
+        }
+        Some(h)
+    }
+
+    fn bounds(&self) -> Option<Aabb> { self.tri.bounds() }
+    fn mat_id(&self) -> usize { self.tri.mat_id }
+    fn area(&self) -> f32 { self.tri.area() }
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3) { self.tri.sample_surface(u) }
+}
+
+pub struct Mesh {
+    tris: Vec<MeshTri>,
+    bvh: Bvh,
+    bounds: Aabb,
+    cdf: Vec<f32>, // área acumulada, para elegir triángulo al muestrear
+    mat_id: usize,
+}
+
+impl Mesh {
+    // Todos los triángulos con el mismo material
+    pub fn new(tris: Vec<MeshTri>, mat_id: usize) -> Self {
+        let bvh = Bvh::build(&tris);
+        let mut bounds = Aabb { min: v_inf(), max: -v_inf(), mat_id };
+        let mut cdf = Vec::with_capacity(tris.len());
+        let mut total = 0.0;
+        for t in &tris {
+            let b = t.tri.bounds().unwrap();
+            bounds.min = Vec3::new(bounds.min.x.min(b.min.x), bounds.min.y.min(b.min.y), bounds.min.z.min(b.min.z));
+            bounds.max = Vec3::new(bounds.max.x.max(b.max.x), bounds.max.y.max(b.max.y), bounds.max.z.max(b.max.z));
+            total += t.area();
+            cdf.push(total);
+        }
+        Self { tris, bvh, bounds, cdf, mat_id }
+    }
+}
+
+impl Shape for Mesh {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        self.bvh.closest(&self.tris, ray, tmin, tmax)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        if self.tris.is_empty() { None } else { Some(self.bounds) }
+    }
+
+    fn mat_id(&self) -> usize { self.mat_id }
+
+    fn area(&self) -> f32 { self.cdf.last().copied().unwrap_or(0.0) }
+
+    // u.x elige el triángulo (por área) y se reusa, reescalado, dentro de él
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3) {
+        let target = u.x * self.area();
+        let i = self.cdf.partition_point(|&c| c < target).min(self.tris.len() - 1);
+        let lo = if i == 0 { 0.0 } else { self.cdf[i - 1] };
+        let ux = ((target - lo) / (self.cdf[i] - lo).max(1e-12)).clamp(0.0, 1.0);
+        self.tris[i].sample_surface(Vec3::new(ux, u.y, u.z))
+    }
+}
+
+fn v_inf() -> Vec3 { Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY) }
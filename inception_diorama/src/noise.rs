@@ -0,0 +1,59 @@
+// src/noise.rs
+// Ruido de valor con semilla: un valor al azar en cada punto entero de la
+// grilla, interpolado suave. fbm suma octavas a el doble de frecuencia.
+
+// Entero → [0,1), mezclando bits (estilo de los hash de PCG)
+fn hash(x: i32, y: i32, seed: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8DA6_B343) ^ (y as u32).wrapping_mul(0xD816_3841) ^ seed.wrapping_mul(0xCB1A_B31F);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2C1B_3C6D);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297A_2D39);
+    h ^= h >> 15;
+    (h >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+}
+
+fn smooth(t: f32) -> f32 { t * t * (3.0 - 2.0 * t) }
+
+// En [0,1)
+pub fn value2(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let (fx, fy) = (smooth(x - x0), smooth(y - y0));
+    let a = hash(ix, iy, seed) + (hash(ix + 1, iy, seed) - hash(ix, iy, seed)) * fx;
+    let b = hash(ix, iy + 1, seed) + (hash(ix + 1, iy + 1, seed) - hash(ix, iy + 1, seed)) * fx;
+    a + (b - a) * fy
+}
+
+// Octavas de `value2`, normalizadas a [0,1)
+pub fn fbm2(x: f32, y: f32, seed: u32, octaves: u32) -> f32 {
+    let (mut sum, mut amp, mut freq, mut norm) = (0.0, 1.0, 1.0, 0.0);
+    for k in 0..octaves {
+        sum += value2(x * freq, y * freq, seed.wrapping_add(k)) * amp;
+        norm += amp;
+        amp *= 0.5;
+        freq *= 2.0;
+    }
+    sum / norm
+}
+
+// Lo mismo en 3D, para texturas en coordenadas de mundo. En [0,1)
+pub fn value3(x: f32, y: f32, z: f32, seed: u32) -> f32 {
+    let z0 = z.floor();
+    let fz = smooth(z - z0);
+    // cada capa entera en z es un ruido 2D con otra semilla
+    let layer = |k: i32| value2(x, y, seed ^ (k as u32).wrapping_mul(0x9E37_79B9));
+    let (a, b) = (layer(z0 as i32), layer(z0 as i32 + 1));
+    a + (b - a) * fz
+}
+
+pub fn fbm3(x: f32, y: f32, z: f32, seed: u32, octaves: u32) -> f32 {
+    let (mut sum, mut amp, mut freq, mut norm) = (0.0, 1.0, 1.0, 0.0);
+    for k in 0..octaves {
+        sum += value3(x * freq, y * freq, z * freq, seed.wrapping_add(k)) * amp;
+        norm += amp;
+        amp *= 0.5;
+        freq *= 2.0;
+    }
+    sum / norm
+}
@@ -0,0 +1,206 @@
+// src/obj.rs
+// Lector de Wavefront OBJ + MTL: lo que exporta Blender para mallas
+// estáticas (v, vt, vn, f, usemtl, mtllib). Los polígonos se abren en
+// abanico; grupos y objetos se ignoran y las caras se juntan por material.
+use std::path::Path;
+use crate::math::{Vec3, v};
+use crate::material::{Material, Kind};
+
+// Índices a las listas del archivo (ya en base 0) de un vértice de cara
+#[derive(Copy, Clone)]
+pub struct Corner { pub v: usize, pub vt: Option<usize>, pub vn: Option<usize> }
+
+pub struct Obj {
+    pub positions: Vec<Vec3>,
+    pub texcoords: Vec<[f32; 2]>,
+    pub normals: Vec<Vec3>,
+    // (material de `usemtl`, triángulos) en el orden en que aparecen
+    pub groups: Vec<(Option<String>, Vec<[Corner; 3]>)>,
+    pub materials: Vec<(String, Material)>,
+}
+
+fn floats<'a>(it: impl Iterator<Item = &'a str>, ln: usize) -> Result<Vec<f32>, String> {
+    it.map(|w| w.parse::<f32>().map_err(|_| format!("línea {ln}: número inválido '{w}'"))).collect()
+}
+
+fn vec3(f: &[f32], ln: usize) -> Result<Vec3, String> {
+    match f { [x, y, z, ..] => Ok(Vec3::new(*x, *y, *z)), _ => Err(format!("línea {ln}: faltan coordenadas")) }
+}
+
+// Índice OBJ (desde 1, o negativo desde el final) a base 0
+fn index(w: &str, len: usize, ln: usize) -> Result<usize, String> {
+    let i: i64 = w.parse().map_err(|_| format!("línea {ln}: índice inválido '{w}'"))?;
+    let k = if i < 0 { len as i64 + i } else { i - 1 };
+    if k < 0 || k >= len as i64 { return Err(format!("línea {ln}: índice {i} fuera de rango")); }
+    Ok(k as usize)
+}
+
+pub fn load(path: &Path) -> Result<Obj, String> {
+    let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut obj = Obj { positions: Vec::new(), texcoords: Vec::new(), normals: Vec::new(), groups: Vec::new(), materials: Vec::new() };
+    let mut current: Option<String> = None;
+
+    for (i, raw) in src.lines().enumerate() {
+        let ln = i + 1;
+        let mut w = raw.split('#').next().unwrap_or("").split_whitespace();
+        let Some(key) = w.next() else { continue };
+        match key {
+            "v"  => obj.positions.push(vec3(&floats(w, ln)?, ln)?),
+            "vn" => obj.normals.push(vec3(&floats(w, ln)?, ln)?.norm()),
+            "vt" => {
+                let f = floats(w, ln)?;
+                obj.texcoords.push([f.first().copied().unwrap_or(0.0), f.get(1).copied().unwrap_or(0.0)]);
+            }
+            "f" => {
+                let mut poly = Vec::new();
+                for c in w {
+                    let mut parts = c.split('/');
+                    let v = index(parts.next().unwrap_or(""), obj.positions.len(), ln)?;
+                    let vt = match parts.next() { Some(s) if !s.is_empty() => Some(index(s, obj.texcoords.len(), ln)?), _ => None };
+                    let vn = match parts.next() { Some(s) if !s.is_empty() => Some(index(s, obj.normals.len(), ln)?), _ => None };
+                    poly.push(Corner { v, vt, vn });
+                }
+                if poly.len() < 3 { return Err(format!("línea {ln}: cara con menos de 3 vértices")); }
+                if obj.groups.last().is_none_or(|(m, _)| *m != current) {
+                    obj.groups.push((current.clone(), Vec::new()));
+                }
+                let tris = &mut obj.groups.last_mut().unwrap().1;
+                for k in 1..poly.len() - 1 { tris.push([poly[0], poly[k], poly[k + 1]]); }
+            }
+            "usemtl" => current = w.next().map(str::to_string),
+            "mtllib" => {
+                for name in w {
+                    let mtl = path.with_file_name(name);
+                    obj.materials.extend(load_mtl(&mtl)?);
+                }
+            }
+            // grupos, objetos, suavizado y lo demás no cambian la geometría
+            _ => {}
+        }
+    }
+    // un mismo material puede volver a aparecer más adelante: se juntan
+    let mut merged: Vec<(Option<String>, Vec<[Corner; 3]>)> = Vec::new();
+    for (m, tris) in obj.groups.drain(..) {
+        match merged.iter_mut().find(|(n, _)| *n == m) {
+            Some((_, t)) => t.extend(tris),
+            None => merged.push((m, tris)),
+        }
+    }
+    obj.groups = merged;
+    Ok(obj)
+}
+
+// Lo que interesa de un `newmtl` para llevarlo a `Material`
+#[derive(Default)]
+struct Mtl { kd: Option<Vec3>, ks: Vec3, ke: Vec3, ns: f32, ni: Option<f32>, d: f32, illum: u32 }
+
+pub fn load_mtl(path: &Path) -> Result<Vec<(String, Material)>, String> {
+    let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut out: Vec<(String, Mtl)> = Vec::new();
+    for (i, raw) in src.lines().enumerate() {
+        let ln = i + 1;
+        let mut w = raw.split('#').next().unwrap_or("").split_whitespace();
+        let Some(key) = w.next() else { continue };
+        if key == "newmtl" {
+            let name = w.next().ok_or(format!("{}:{ln}: newmtl sin nombre", path.display()))?;
+            out.push((name.to_string(), Mtl { d: 1.0, ns: 10.0, ..Mtl::default() }));
+            continue;
+        }
+        let Some((_, m)) = out.last_mut() else { continue };
+        let f = || floats(w.clone(), ln).map_err(|e| format!("{}: {e}", path.display()));
+        match key {
+            "Kd" => m.kd = Some(vec3(&f()?, ln)?),
+            "Ks" => m.ks = vec3(&f()?, ln)?,
+            "Ke" => m.ke = vec3(&f()?, ln)?,
+            "Ns" => m.ns = f()?.first().copied().unwrap_or(10.0),
+            "Ni" => m.ni = f()?.first().copied(),
+            "d"  => m.d = f()?.first().copied().unwrap_or(1.0),
+            "Tr" => m.d = 1.0 - f()?.first().copied().unwrap_or(0.0),
+            "illum" => m.illum = f()?.first().copied().unwrap_or(2.0) as u32,
+            _ => {}
+        }
+    }
+    Ok(out.into_iter().map(|(n, m)| (n, to_material(&m))).collect())
+}
+
+// MTL es un modelo de Phong: se elige el Kind que más se le parece
+fn to_material(m: &Mtl) -> Material {
+    let kd = m.kd.unwrap_or(v(0.8));
+    let ks = (m.ks.x + m.ks.y + m.ks.z) / 3.0;
+    // Ns (0..1000) a rugosidad, como hace Blender al exportar
+    let rough = (1.0 - (m.ns / 1000.0).clamp(0.0, 1.0).sqrt()).clamp(0.02, 1.0);
+    let emit = m.ke.x.max(m.ke.y).max(m.ke.z);
+    let mut mat = Material { kind: Kind::Diffuse, albedo: kd, specular: ks, transparency: 0.0, reflectivity: 0.0, texture: None, relief: None };
+    if emit > 0.0 {
+        mat.kind = Kind::Emissive { intensity: emit };
+        mat.albedo = m.ke / emit;
+    } else if m.d < 1.0 || matches!(m.illum, 4 | 6 | 7 | 9) {
+        mat.kind = Kind::Dielectric { ior: m.ni.unwrap_or(1.5), absorption: Vec3::default() };
+        mat.albedo = v(1.0);
+        mat.transparency = if m.d < 1.0 { 1.0 - m.d } else { 1.0 };
+    } else if m.illum == 3 {
+        mat.kind = Kind::Metal { rough };
+        mat.albedo = if ks > 0.0 { m.ks } else { kd };
+        mat.reflectivity = 0.9;
+    } else if ks > 0.0 {
+        mat.kind = Kind::Plastic { rough };
+        mat.reflectivity = 0.04;
+    }
+    mat
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Escribe `src` (y un .mtl al lado) en un directorio temporal y lo carga
+    fn load_src(name: &str, src: &str, mtl: &str) -> Result<Obj, String> {
+        let dir = std::env::temp_dir().join(format!("obj_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("{name}.mtl")), mtl).unwrap();
+        let path = dir.join(format!("{name}.obj"));
+        std::fs::write(&path, src).unwrap();
+        load(&path)
+    }
+
+    const QUAD: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvn 0 0 2\n";
+
+    #[test]
+    fn fans_and_indices() {
+        let obj = load_src("fan", &format!("{QUAD}f 1 2 3 4\nf -4/1/1 -3/-1/-1 -2//1 # comentario\n"), "").unwrap();
+        assert_eq!(obj.normals[0].z, 1.0);
+        let tris = &obj.groups[0].1;
+        let ids: Vec<[usize; 3]> = tris.iter().map(|t| t.map(|c| c.v)).collect();
+        assert_eq!(ids, vec![[0, 1, 2], [0, 2, 3], [0, 1, 2]]);
+        assert_eq!((tris[2][0].vt, tris[2][0].vn), (Some(0), Some(0)));
+        assert_eq!((tris[2][2].vt, tris[2][2].vn), (None, Some(0)));
+    }
+
+    #[test]
+    fn groups_by_material() {
+        let mtl = "newmtl a\nKd 1 0 0\nnewmtl b\nKe 0 4 0\nnewmtl c\nd 0.5\nNi 1.33\nnewmtl d\nillum 3\nKs 0.9 0.9 0.9\n";
+        let src = format!("mtllib m.mtl\n{QUAD}usemtl a\nf 1 2 3\nusemtl b\nf 1 3 4\nusemtl a\nf 2 3 4\n");
+        let obj = load_src("m", &src, mtl).unwrap();
+        let names: Vec<_> = obj.groups.iter().map(|(m, t)| (m.as_deref(), t.len())).collect();
+        assert_eq!(names, vec![(Some("a"), 2), (Some("b"), 1)]);
+        let kind = |n: &str| obj.materials.iter().find(|(m, _)| m == n).unwrap().1.kind;
+        assert!(matches!(kind("a"), Kind::Diffuse));
+        assert!(matches!(kind("b"), Kind::Emissive { intensity } if intensity == 4.0));
+        assert!(matches!(kind("c"), Kind::Dielectric { ior, .. } if ior == 1.33));
+        assert!(matches!(kind("d"), Kind::Metal { .. }));
+    }
+
+    #[test]
+    fn malformed_files() {
+        let bad = |src: &str| load_src("bad", &format!("{QUAD}{src}\n"), "").err().unwrap_or_else(|| panic!("se aceptó: {src}"));
+        assert!(bad("f 1 2 5").contains("fuera de rango"));
+        assert!(bad("f 1 2 -5").contains("fuera de rango"));
+        assert!(bad("f 0 1 2").contains("fuera de rango"));
+        assert!(bad("f 1/2 2 3").contains("fuera de rango"));
+        assert!(bad("f 1 2").contains("menos de 3"));
+        assert!(bad("f 1 a 3").contains("índice inválido"));
+        assert!(bad("v 1 2").contains("faltan coordenadas"));
+        assert!(bad("vn 1 x 2").contains("número inválido"));
+        assert!(bad("mtllib falta.mtl").contains("falta.mtl"));
+    }
+}
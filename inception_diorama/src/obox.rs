@@ -0,0 +1,82 @@
+// src/obox.rs
+// Caja orientada: centro, medio tamaño por eje y una rotación cualquiera.
+// El rayo se pasa a los ejes de la caja y ahí es un slab test como el de
+// `Aabb`, pero la cara se sabe por el eje que dio la t, sin epsilon.
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::shape::Shape;
+use crate::transform::quat;
+
+#[derive(Copy, Clone)]
+pub struct OrientedBox {
+    pub center: Vec3,
+    pub half: Vec3,
+    pub rot: [f32; 4], // cuaternión (w, x, y, z)
+    pub mat_id: usize,
+    axes: [Vec3; 3],   // columnas de la rotación: los ejes locales en el mundo
+}
+
+fn comp(v: Vec3, i: usize) -> f32 { match i { 0 => v.x, 1 => v.y, _ => v.z } }
+
+impl OrientedBox {
+    pub fn new(center: Vec3, size: Vec3, rot: [f32; 4], mat_id: usize) -> Self {
+        let m = quat(rot);
+        let axes = [0, 1, 2].map(|j| Vec3::new(m[0][j], m[1][j], m[2][j]));
+        Self { center, half: size * 0.5, rot, mat_id, axes }
+    }
+}
+
+impl Shape for OrientedBox {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        let o = ray.o - self.center;
+        let lo = self.axes.map(|a| o.dot(a));
+        let ld = self.axes.map(|a| ray.d.dot(a));
+        // (t, eje, signo de la cara) de entrada y de salida
+        let (mut enter, mut exit) = ((f32::NEG_INFINITY, 0, 0.0), (f32::INFINITY, 0, 0.0));
+        for i in 0..3 {
+            let h = comp(self.half, i);
+            if ld[i].abs() < 1e-12 {
+                if lo[i].abs() > h { return None; }
+                continue;
+            }
+            let s = ld[i].signum();
+            // se entra por la cara que mira contra el rayo y se sale por la otra
+            let t0 = (-s * h - lo[i]) / ld[i];
+            let t1 = (s * h - lo[i]) / ld[i];
+            if t0 > enter.0 { enter = (t0, i, -s); }
+            if t1 < exit.0 { exit = (t1, i, s); }
+        }
+        if exit.0 <= enter.0 { return None; }
+        // como en `Aabb`: con el origen adentro, el impacto es la salida
+        let (t, i, s) = if enter.0 > tmin && enter.0 < tmax { enter } else if exit.0 > tmin && exit.0 < tmax { exit } else { return None };
+
+        // uv: las otras dos coordenadas locales de la cara, llevadas a [0,1]
+        let r = [0, 1, 2].map(|k| 0.5 + (lo[k] + t * ld[k]) / (2.0 * comp(self.half, k)).max(1e-8));
+        let uv = match i { 0 => [r[2], r[1]], 1 => [r[0], r[2]], _ => [r[0], r[1]] };
+        Some(Hit { t, p: ray.at(t), n: self.axes[i] * s, mat_id: self.mat_id, uv })
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let [a, b, c] = self.axes;
+        let h = self.half;
+        let abs = |v: Vec3| Vec3::new(v.x.abs(), v.y.abs(), v.z.abs());
+        let e = abs(a) * h.x + abs(b) * h.y + abs(c) * h.z;
+        Some(Aabb { min: self.center - e, max: self.center + e, mat_id: self.mat_id })
+    }
+
+    fn mat_id(&self) -> usize { self.mat_id }
+
+    fn area(&self) -> f32 {
+        let h = self.half;
+        8.0 * (h.x * h.y + h.y * h.z + h.z * h.x)
+    }
+
+    // La caja local centrada en el origen, muestreada como un `Aabb`, y rotada
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3) {
+        let local = Aabb { min: -self.half, max: self.half, mat_id: self.mat_id };
+        let (p, n) = local.sample_surface(u);
+        let [a, b, c] = self.axes;
+        (self.center + a * p.x + b * p.y + c * p.z, a * n.x + b * n.y + c * n.z)
+    }
+}
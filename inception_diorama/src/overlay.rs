@@ -0,0 +1,101 @@
+// src/overlay.rs
+// Texto en pantalla con una fuente de mapa de bits 5x7 (solo mayúsculas,
+// dígitos y algo de puntuación; las minúsculas se dibujan en mayúscula).
+
+const GW: usize = 5;
+const GH: usize = 7;
+const SCALE: usize = 2; // cada píxel de la fuente ocupa 2x2
+
+fn glyph(c: char) -> [u8; GH] {
+    match c {
+        'A' => [0b01110,0b10001,0b10001,0b11111,0b10001,0b10001,0b10001],
+        'B' => [0b11110,0b10001,0b10001,0b11110,0b10001,0b10001,0b11110],
+        'C' => [0b01110,0b10001,0b10000,0b10000,0b10000,0b10001,0b01110],
+        'D' => [0b11100,0b10010,0b10001,0b10001,0b10001,0b10010,0b11100],
+        'E' => [0b11111,0b10000,0b10000,0b11110,0b10000,0b10000,0b11111],
+        'F' => [0b11111,0b10000,0b10000,0b11110,0b10000,0b10000,0b10000],
+        'G' => [0b01110,0b10001,0b10000,0b10111,0b10001,0b10001,0b01111],
+        'H' => [0b10001,0b10001,0b10001,0b11111,0b10001,0b10001,0b10001],
+        'I' => [0b01110,0b00100,0b00100,0b00100,0b00100,0b00100,0b01110],
+        'J' => [0b00111,0b00010,0b00010,0b00010,0b00010,0b10010,0b01100],
+        'K' => [0b10001,0b10010,0b10100,0b11000,0b10100,0b10010,0b10001],
+        'L' => [0b10000,0b10000,0b10000,0b10000,0b10000,0b10000,0b11111],
+        'M' => [0b10001,0b11011,0b10101,0b10101,0b10001,0b10001,0b10001],
+        'N' => [0b10001,0b10001,0b11001,0b10101,0b10011,0b10001,0b10001],
+        'O' => [0b01110,0b10001,0b10001,0b10001,0b10001,0b10001,0b01110],
+        'P' => [0b11110,0b10001,0b10001,0b11110,0b10000,0b10000,0b10000],
+        'Q' => [0b01110,0b10001,0b10001,0b10001,0b10101,0b10010,0b01101],
+        'R' => [0b11110,0b10001,0b10001,0b11110,0b10100,0b10010,0b10001],
+        'S' => [0b01111,0b10000,0b10000,0b01110,0b00001,0b00001,0b11110],
+        'T' => [0b11111,0b00100,0b00100,0b00100,0b00100,0b00100,0b00100],
+        'U' => [0b10001,0b10001,0b10001,0b10001,0b10001,0b10001,0b01110],
+        'V' => [0b10001,0b10001,0b10001,0b10001,0b10001,0b01010,0b00100],
+        'W' => [0b10001,0b10001,0b10001,0b10101,0b10101,0b10101,0b01010],
+        'X' => [0b10001,0b10001,0b01010,0b00100,0b01010,0b10001,0b10001],
+        'Y' => [0b10001,0b10001,0b10001,0b01010,0b00100,0b00100,0b00100],
+        'Z' => [0b11111,0b00001,0b00010,0b00100,0b01000,0b10000,0b11111],
+        '0' => [0b01110,0b10001,0b10011,0b10101,0b11001,0b10001,0b01110],
+        '1' => [0b00100,0b01100,0b00100,0b00100,0b00100,0b00100,0b01110],
+        '2' => [0b01110,0b10001,0b00001,0b00010,0b00100,0b01000,0b11111],
+        '3' => [0b11111,0b00010,0b00100,0b00010,0b00001,0b10001,0b01110],
+        '4' => [0b00010,0b00110,0b01010,0b10010,0b11111,0b00010,0b00010],
+        '5' => [0b11111,0b10000,0b11110,0b00001,0b00001,0b10001,0b01110],
+        '6' => [0b00110,0b01000,0b10000,0b11110,0b10001,0b10001,0b01110],
+        '7' => [0b11111,0b00001,0b00010,0b00100,0b01000,0b01000,0b01000],
+        '8' => [0b01110,0b10001,0b10001,0b01110,0b10001,0b10001,0b01110],
+        '9' => [0b01110,0b10001,0b10001,0b01111,0b00001,0b00010,0b01100],
+        ':' => [0b00000,0b01100,0b01100,0b00000,0b01100,0b01100,0b00000],
+        '.' => [0b00000,0b00000,0b00000,0b00000,0b00000,0b01100,0b01100],
+        ',' => [0b00000,0b00000,0b00000,0b00000,0b01100,0b00100,0b01000],
+        '-' => [0b00000,0b00000,0b00000,0b11111,0b00000,0b00000,0b00000],
+        '_' => [0b00000,0b00000,0b00000,0b00000,0b00000,0b00000,0b11111],
+        '+' => [0b00000,0b00100,0b00100,0b11111,0b00100,0b00100,0b00000],
+        '=' => [0b00000,0b00000,0b11111,0b00000,0b11111,0b00000,0b00000],
+        '\'' => [0b00100,0b00100,0b01000,0b00000,0b00000,0b00000,0b00000],
+        '"' => [0b01010,0b01010,0b00000,0b00000,0b00000,0b00000,0b00000],
+        '#' => [0b01010,0b01010,0b11111,0b01010,0b11111,0b01010,0b01010],
+        '/' => [0b00001,0b00010,0b00010,0b00100,0b01000,0b01000,0b10000],
+        '(' => [0b00010,0b00100,0b01000,0b01000,0b01000,0b00100,0b00010],
+        ')' => [0b01000,0b00100,0b00010,0b00010,0b00010,0b00100,0b01000],
+        '?' => [0b01110,0b10001,0b00001,0b00010,0b00100,0b00000,0b00100],
+        '!' => [0b00100,0b00100,0b00100,0b00100,0b00100,0b00000,0b00100],
+        ' ' => [0; GH],
+        _   => [0b11111,0b10001,0b10001,0b10001,0b10001,0b10001,0b11111],
+    }
+}
+
+// Quita acentos para que los mensajes en español se lean
+fn fold(c: char) -> char {
+    match c {
+        'á' | 'Á' => 'A', 'é' | 'É' => 'E', 'í' | 'Í' => 'I',
+        'ó' | 'Ó' => 'O', 'ú' | 'Ú' | 'ü' | 'Ü' => 'U', 'ñ' | 'Ñ' => 'N',
+        _ => c.to_ascii_uppercase(),
+    }
+}
+
+// Dibuja una línea de texto con fondo oscuro.
+// Lo que no entra en el ancho se corta.
+pub fn draw_text(fb: &mut [u32], w: usize, h: usize, x0: usize, y0: usize, text: &str, color: u32) {
+    let adv = (GW + 1) * SCALE;
+    let n = text.chars().count();
+    let (bw, bh) = ((n * adv + SCALE).min(w.saturating_sub(x0)), (GH + 2) * SCALE);
+    for y in y0..(y0 + bh).min(h) {
+        for x in x0..x0 + bw {
+            let p = &mut fb[y*w + x];
+            *p = (*p >> 2) & 0x3f3f3f | 0xff00_0000; // oscurece el fondo
+        }
+    }
+    for (k, c) in text.chars().map(fold).enumerate() {
+        let g = glyph(c);
+        let gx = x0 + SCALE + k * adv;
+        for (row, bits) in g.iter().enumerate() {
+            for col in 0..GW {
+                if bits & (1 << (GW - 1 - col)) == 0 { continue; }
+                for sy in 0..SCALE { for sx in 0..SCALE {
+                    let (x, y) = (gx + col*SCALE + sx, y0 + SCALE + row*SCALE + sy);
+                    if x < w && y < h { fb[y*w + x] = color; }
+                }}
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// src/pathtrace.rs
+// Path tracer Monte Carlo: rebotes difusos con densidad coseno, los
+// brillantes con la GGX, y estimación directa (NEE) hacia las luces y
+// hacia los objetos emisivos.
+use crate::math::{Vec3, v, reflect, refract, fresnel_schlick, lerp};
+use crate::ray::Ray;
+use crate::aabb::Hit;
+use crate::material::{Material, Kind, beer};
+use crate::ggx::Ggx;
+use crate::rng::Rng;
+use crate::scene::Scene;
+use crate::shape::Shape;
+
+const EPS: f32 = 1e-3;
+const RR_START: u32 = 3; // ruleta rusa a partir de este rebote
+
+fn emission(mat: &Material) -> Vec3 {
+    match mat.kind { Kind::Emissive { intensity } => mat.albedo * intensity, _ => v(0.0) }
+}
+
+// Luz directa sobre un punto difuso de albedo `albedo`, más el lóbulo
+// `spec` si lo tiene. Las luces de la escena dan irradiancia (sin el 1/π,
+// como en el modo Whitted); una muestra al azar por luz, que atraviesa los
+// vidrios. Las luces no son geometría, así que el lóbulo especular solo las
+// ve por acá; a los objetos emisivos los encuentra rebotando (también a
+// través de un vidrio) y se cuentan solo para el difuso.
+fn direct(scene: &Scene, h: &Hit, n: Vec3, albedo: Vec3, spec: Option<&Ggx>, rng: &mut Rng) -> Vec3 {
+    let o = h.p + n * EPS;
+    let mut l = v(0.0);
+
+    for light in scene.lights() {
+        let Some(s) = light.sample(o, rng.f32(), rng.f32()) else { continue };
+        let ndotl = n.dot(s.wi);
+        if ndotl <= 0.0 { continue; }
+        let e = s.e * scene.shadow(&Ray::new(o, s.wi), EPS, s.dist.min(1e9) - EPS);
+        l = l + albedo * e * ndotl;
+        if let Some(g) = spec { l = l + g.eval(s.wi) * e; }
+    }
+
+    // Un objeto emisivo elegido al azar, punto uniforme sobre su superficie
+    let em = scene.emitters();
+    if !em.is_empty() && albedo.x.max(albedo.y).max(albedo.z) > 0.0 {
+        let k = ((rng.f32() * em.len() as f32) as usize).min(em.len() - 1);
+        let b = &scene.objects[em[k]];
+        let (p, ln) = b.sample_surface(Vec3::new(rng.f32(), rng.f32(), rng.f32()));
+        let to = p - o;
+        let dist2 = to.dot(to);
+        let dist = dist2.sqrt();
+        let wi = to / dist;
+        let cos_s = n.dot(wi);
+        let cos_l = ln.dot(-wi);
+        if cos_s > 0.0 && cos_l > 0.0 && !scene.occluded(&Ray::new(o, wi), EPS, dist - 2.0*EPS) {
+            // pdf en ángulo sólido = dist² / (cos_l · área), por 1/N de elegir el objeto
+            let pdf = dist2 / (cos_l * b.area()) / em.len() as f32;
+            let le = emission(&scene.mats[b.mat_id()]);
+            l = l + albedo * le * (cos_s / std::f32::consts::PI / pdf);
+        }
+    }
+    l
+}
+
+// Radiancia a lo largo de un rayo de cámara
+pub fn radiance(scene: &Scene, ray: &Ray, rng: &mut Rng, max_bounces: u32) -> Vec3 {
+    let mut ray = *ray;
+    let mut col = v(0.0);
+    let mut beta = v(1.0); // throughput
+    let mut specular = true; // el rebote anterior no pasó por NEE
+    let mut medium: Option<Vec3> = None; // la absorción del dieléctrico en el que se está
+
+    for bounce in 0..max_bounces {
+        let hit = scene.hit(&ray, EPS, 1e9);
+        // Beer-Lambert con lo que se recorrió adentro hasta acá
+        if let Some(a) = medium { beta = beta * beer(a, hit.as_ref().map_or(f32::INFINITY, |h| h.t)); }
+        let Some(mut h) = hit else {
+            col = col + beta * scene.sky(ray.d);
+            break;
+        };
+        if h.n.dot(h.n) < 0.5 { break; } // normal degenerada en una arista
+        let mat = &scene.material(&ray, &mut h);
+        let front = ray.d.dot(h.n) < 0.0;
+        let n = if front { h.n } else { -h.n };
+
+        let view = -ray.d;
+        let cos_theta = n.dot(view).max(0.0);
+
+        let (d, o) = match mat.kind {
+            Kind::Emissive { .. } => {
+                // ya lo cuenta NEE, salvo en el primer impacto o tras un espejo
+                if specular { col = col + beta * emission(mat); }
+                break;
+            }
+            Kind::Diffuse => {
+                // reflejo especular con probabilidad `reflectivity`, si no difuso
+                if mat.reflectivity > 0.01 && rng.f32() < mat.reflectivity {
+                    specular = true;
+                    (reflect(ray.d, n).norm(), h.p + n * EPS)
+                } else {
+                    specular = false;
+                    col = col + beta * direct(scene, &h, n, mat.albedo, None, rng);
+                    beta = beta * mat.albedo;
+                    (rng.cosine_dir(n), h.p + n * EPS)
+                }
+            }
+            Kind::Metal { rough } | Kind::Plastic { rough } => {
+                let f0 = lerp(v(0.04), mat.albedo, mat.reflectivity.clamp(0.0, 1.0));
+                let lobe = Ggx::new(n, view, rough, f0);
+                // el plástico pasa al difuso lo que no refleja la capa especular
+                let kd = match mat.kind {
+                    Kind::Plastic { .. } => mat.albedo * (v(1.0) - lobe.albedo()),
+                    _ => v(0.0),
+                };
+                col = col + beta * direct(scene, &h, n, kd, Some(&lobe), rng);
+                // se elige el lóbulo según cuánto se lleva el especular
+                let e = lobe.albedo();
+                let ps = if kd.x.max(kd.y).max(kd.z) > 0.0 { ((e.x + e.y + e.z) / 3.0).clamp(0.05, 0.95) } else { 1.0 };
+                if rng.f32() < ps {
+                    specular = true;
+                    let Some((d, w)) = lobe.sample(rng.f32(), rng.f32()) else { break };
+                    beta = beta * w / ps;
+                    (d, h.p + n * EPS)
+                } else {
+                    specular = false;
+                    beta = beta * kd / (1.0 - ps);
+                    (rng.cosine_dir(n), h.p + n * EPS)
+                }
+            }
+            Kind::Dielectric { ior, absorption } => {
+                specular = true;
+                let eta = if front { 1.0 / ior } else { ior };
+                let fr = fresnel_schlick(cos_theta, v(((1.0 - ior) / (1.0 + ior)).powi(2))).x;
+                beta = beta * mat.albedo;
+                match refract(ray.d, n, eta) {
+                    Some(t) if rng.f32() >= fr => {
+                        // al cruzar se entra o se sale del medio
+                        medium = front.then_some(absorption);
+                        (t.norm(), h.p - n * EPS)
+                    }
+                    _ => (reflect(ray.d, n).norm(), h.p + n * EPS),
+                }
+            }
+        };
+        ray = Ray { o, d, cone: ray.cone.after(h.t) };
+
+        if bounce >= RR_START {
+            let p = beta.x.max(beta.y).max(beta.z).clamp(0.05, 0.95);
+            if rng.f32() > p { break; }
+            beta = beta / p;
+        }
+    }
+    col
+}
@@ -0,0 +1,36 @@
+// src/plane.rs
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::rng::onb;
+use crate::shape::Shape;
+
+// Plano infinito por `point` con normal `normal` (no hace falta normalizada)
+#[derive(Copy, Clone)]
+pub struct Plane { pub point: Vec3, pub normal: Vec3, pub mat_id: usize }
+
+impl Shape for Plane {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        let n = self.normal.norm();
+        let denom = ray.d.dot(n);
+        if denom.abs() < 1e-8 { return None; }
+        let t = (self.point - ray.o).dot(n) / denom;
+        if t <= tmin || t >= tmax { return None; }
+        let p = ray.at(t);
+        // uv en unidades de mundo, repetida cada 1 unidad
+        let (tu, tv) = onb(n);
+        let d = p - self.point;
+        let uv = [d.dot(tu).rem_euclid(1.0), d.dot(tv).rem_euclid(1.0)];
+        Some(Hit { t, p, n, mat_id: self.mat_id, uv })
+    }
+
+    fn bounds(&self) -> Option<Aabb> { None }
+
+    fn mat_id(&self) -> usize { self.mat_id }
+
+    fn area(&self) -> f32 { f32::INFINITY }
+
+    // Un plano infinito no se puede muestrear por área; nunca se le pide
+    // porque no entra en la lista de emisores.
+    fn sample_surface(&self, _u: Vec3) -> (Vec3, Vec3) { (self.point, self.normal.norm()) }
+}
@@ -1,5 +1,21 @@
 // src/ray.rs
 use crate::math::Vec3;
+
+// Cono alrededor del rayo: el ancho del píxel en el origen y cuánto crece
+// por unidad de t (los rayos que lo llevan tienen `d` unitaria). Con eso
+// las texturas eligen el nivel de mipmap; los rayos de sombra no lo usan.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Cone { pub width: f32, pub spread: f32 }
+
+impl Cone {
+    pub fn width_at(&self, t: f32) -> f32 { self.width + self.spread * t }
+    // El mismo cono, arrancando donde el rayo pegó (no se tiene en cuenta la curvatura)
+    pub fn after(&self, t: f32) -> Self { Self { width: self.width_at(t), spread: self.spread } }
+}
+
 #[derive(Copy, Clone, Debug)]
-pub struct Ray { pub o: Vec3, pub d: Vec3 }
-impl Ray { pub fn at(&self, t:f32)->Vec3{ self.o + self.d*t } }
\ No newline at end of file
+pub struct Ray { pub o: Vec3, pub d: Vec3, pub cone: Cone }
+impl Ray {
+    pub fn new(o: Vec3, d: Vec3) -> Self { Self { o, d, cone: Cone::default() } }
+    pub fn at(&self, t:f32)->Vec3{ self.o + self.d*t }
+}
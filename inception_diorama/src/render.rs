@@ -0,0 +1,194 @@
+// src/render.rs
+use std::sync::Mutex;
+use std::thread;
+use crate::camera::Camera;
+use crate::math::Vec3;
+use crate::pathtrace;
+use crate::rng::Rng;
+use crate::scene::Scene;
+use crate::tonemap::Display;
+
+// Filas por banda: bandas chicas reparten mejor la carga entre hilos
+const BAND_ROWS: usize = 8;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Mode {
+    Whitted,   // sol + sombra + ambiente, rápido
+    PathTrace, // iluminación global Monte Carlo
+}
+
+pub struct Renderer {
+    pub threads: usize,
+    pub max_depth: i32,
+    pub spp: u32, // muestras por píxel
+    pub mode: Mode,
+    pub max_bounces: u32, // solo para PathTrace
+}
+
+// Suma de muestras por píxel que se va acumulando cuadro a cuadro
+pub struct Accum {
+    pub sum: Vec<Vec3>,
+    pub frames: u32,
+}
+
+impl Accum {
+    pub fn new(w: usize, h: usize) -> Self {
+        Self { sum: vec![Vec3::default(); w*h], frames: 0 }
+    }
+
+    pub fn reset(&mut self, w: usize, h: usize) {
+        self.sum.clear();
+        self.sum.resize(w*h, Vec3::default());
+        self.frames = 0;
+    }
+
+    // Promedio a formato de ventana
+    pub fn resolve(&self, display: &Display, fb: &mut [u32]) {
+        display.resolve(&self.sum, 1.0 / self.frames.max(1) as f32, fb);
+    }
+}
+
+// Reparte `buf` en bandas de filas entre hilos. Cada píxel depende solo
+// de (i, j), así que el resultado es el mismo sin importar qué hilo tome
+// cada banda.
+fn par_bands<T: Send>(threads: usize, w: usize, buf: &mut [T], f: impl Fn(usize, &mut [T]) + Sync) {
+    let bands = Mutex::new(buf.chunks_mut(w * BAND_ROWS).enumerate());
+    thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            s.spawn(|| loop {
+                let next = bands.lock().unwrap().next();
+                let Some((b, band)) = next else { break };
+                f(b * BAND_ROWS, band);
+            });
+        }
+    });
+}
+
+impl Renderer {
+    // threads = 0 usa todos los núcleos disponibles
+    pub fn new(threads: usize) -> Self {
+        let threads = if threads == 0 {
+            thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+        } else { threads };
+        // vidrio y metal necesitan rebotes
+        Self { threads, max_depth: 4, spp: 1, mode: Mode::Whitted, max_bounces: 8 }
+    }
+
+    // Imagen completa con `spp` muestras por píxel, en radiancia lineal
+    pub fn render(&self, scene: &Scene, cam: &Camera, w: usize, h: usize, hdr: &mut [Vec3]) {
+        assert_eq!(hdr.len(), w * h);
+        par_bands(self.threads, w, hdr, |j0, band| {
+            for (row, line) in band.chunks_mut(w).enumerate() {
+                for (i, px) in line.iter_mut().enumerate() {
+                    let mut acc = Vec3::default();
+                    for k in 0..self.spp {
+                        acc = acc + self.sample(scene, cam, (w, h), (i, j0 + row), k);
+                    }
+                    *px = acc / self.spp as f32;
+                }
+            }
+        });
+    }
+
+    // Suma una muestra más por píxel a `acc` (modo progresivo del visor)
+    pub fn accumulate(&self, scene: &Scene, cam: &Camera, w: usize, h: usize, acc: &mut Accum) {
+        assert_eq!(acc.sum.len(), w * h);
+        let k = acc.frames;
+        par_bands(self.threads, w, &mut acc.sum, |j0, band| {
+            for (row, line) in band.chunks_mut(w).enumerate() {
+                for (i, px) in line.iter_mut().enumerate() {
+                    *px = *px + self.sample(scene, cam, (w, h), (i, j0 + row), k);
+                }
+            }
+        });
+        acc.frames += 1;
+    }
+
+    // Muestra k del píxel (i, j)
+    fn sample(&self, scene: &Scene, cam: &Camera, (w, h): (usize, usize), (i, j): (usize, usize), k: u32) -> Vec3 {
+        let inv_w = 1.0 / w as f32;
+        let inv_h = 1.0 / h as f32;
+        // lo que se abre el píxel por unidad recorrida, para los mipmaps
+        let spread = 2.0 * cam.half_h * inv_h;
+        match self.mode {
+            Mode::Whitted => {
+                // Supermuestreo con la secuencia R2: fija, así que determinista.
+                // La muestra 0 cae en la esquina del píxel, como siempre.
+                let (ox, oy) = if k == 0 { (0.0, 0.0) } else { r2(k) };
+                let x = ((i as f32 + ox)*inv_w)*2.0 - 1.0; // [-1,1]
+                let y = ((j as f32 + oy)*inv_h)*2.0 - 1.0;
+                let mut ray = cam.ray_for(x, -y); // y invertida para imagen
+                ray.cone.spread = spread;
+                scene.trace(&ray, self.max_depth)
+            }
+            Mode::PathTrace => {
+                let mut rng = Rng::for_pixel(i, j, k);
+                let x = ((i as f32 + rng.f32())*inv_w)*2.0 - 1.0;
+                let y = ((j as f32 + rng.f32())*inv_h)*2.0 - 1.0;
+                let mut ray = cam.ray_for(x, -y);
+                ray.cone.spread = spread;
+                pathtrace::radiance(scene, &ray, &mut rng, self.max_bounces)
+            }
+        }
+    }
+}
+
+// Punto k de la secuencia R2 en [0,1)^2
+fn r2(k: u32) -> (f32, f32) {
+    const A1: f64 = 0.754_877_666_246_692_7;
+    const A2: f64 = 0.569_840_290_998_053_3;
+    let k = k as f64;
+    ((0.5 + A1*k).fract() as f32, (0.5 + A2*k).fract() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    fn render(threads: usize) -> Vec<u32> {
+        let scene = Scene::test_scene();
+        let (w, h) = (64, 36);
+        let cam = Camera::from_euler(Vec3::new(0.0, 0.0, 12.0), 0.0, 0.0, 0.0, 60.0, w as f32 / h as f32);
+        let mut hdr = vec![Vec3::default(); w * h];
+        Renderer::new(threads).render(&scene, &cam, w, h, &mut hdr);
+        let mut fb = vec![0u32; w * h];
+        Display::default().resolve(&hdr, 1.0, &mut fb);
+        fb
+    }
+
+    // Las bandas se reparten entre hilos, pero cada píxel sale igual
+    #[test]
+    fn same_image_with_any_thread_count() {
+        assert_eq!(render(1), render(3));
+    }
+
+    // Lo que llega a un rayo que baja sobre el piso en (x, z), en cada modo
+    fn down(scene: &Scene, x: f32, z: f32, mode: Mode) -> Vec3 {
+        let ray = Ray::new(Vec3::new(x, 5.0, z), Vec3::new(0.0, -1.0, 0.0));
+        match mode {
+            Mode::Whitted => scene.trace(&ray, 4),
+            Mode::PathTrace => {
+                let n = 256;
+                (0..n).fold(Vec3::default(), |acc, k| acc + pathtrace::radiance(scene, &ray, &mut Rng::for_pixel(0, 0, k), 8)) / n as f32
+            }
+        }
+    }
+
+    // Un cubo sobre un piso, con el sol de costado: la sombra cae hacia +x +z
+    #[test]
+    fn box_casts_shadow_in_both_modes() {
+        let scene = crate::scene_file::parse("light directional dir 0.4 -0.7 0.3 color 1 1 1 intensity 1.8\n\
+            material gray diffuse albedo 0.5 0.5 0.5\n\
+            box gray -5 -1 -5  5 0 5\nbox gray -0.5 0 -0.5  0.5 1 0.5\n", std::path::Path::new(".")).unwrap();
+        for mode in [Mode::Whitted, Mode::PathTrace] {
+            let (lit, shadow) = (down(&scene, -2.0, -2.0, mode), down(&scene, 0.7, 0.5, mode));
+            assert!(lit.y > 2.0 * shadow.y, "{mode:?}: {lit:?} {shadow:?}");
+            // fuera del piso se ve el cielo
+            let up = Ray::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+            let sky = match mode { Mode::Whitted => scene.trace(&up, 4), Mode::PathTrace => pathtrace::radiance(&scene, &up, &mut Rng::for_pixel(0, 0, 0), 8) };
+            let e = sky - scene.sky(up.d);
+            assert!(e.dot(e) < 1e-12, "{mode:?}: {sky:?}");
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// src/rng.rs
+use crate::math::Vec3;
+
+// PCG32: chico, rápido y reproducible a partir de una semilla
+pub struct Rng { state: u64 }
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut r = Rng { state: 0 };
+        r.next_u32();
+        r.state = r.state.wrapping_add(seed);
+        r.next_u32();
+        r
+    }
+
+    // Semilla por píxel y cuadro, para que cada muestra sea determinista
+    pub fn for_pixel(i: usize, j: usize, frame: u32) -> Self {
+        let h = (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (j as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            ^ (frame as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+        Self::new(h)
+    }
+
+    // Semilla a partir de un punto de la escena (sombreado Whitted)
+    pub fn for_point(p: Vec3) -> Self {
+        let h = (p.x.to_bits() as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (p.y.to_bits() as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            ^ (p.z.to_bits() as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+        Self::new(h)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    // Uniforme en [0,1)
+    pub fn f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    // Dirección con densidad coseno alrededor de n
+    pub fn cosine_dir(&mut self, n: Vec3) -> Vec3 {
+        let (r1, r2) = (self.f32(), self.f32());
+        let phi = 2.0 * std::f32::consts::PI * r1;
+        let r = r2.sqrt();
+        let (t, b) = onb(n);
+        (t * (r * phi.cos()) + b * (r * phi.sin()) + n * (1.0 - r2).max(0.0).sqrt()).norm()
+    }
+}
+
+// Base ortonormal (t, b) alrededor de n (Duff et al. 2017)
+pub fn onb(n: Vec3) -> (Vec3, Vec3) {
+    let s = if n.z >= 0.0 { 1.0 } else { -1.0 };
+    let a = -1.0 / (s + n.z);
+    let b = n.x * n.y * a;
+    (Vec3::new(1.0 + s * n.x * n.x * a, s * b, -s * n.x), Vec3::new(b, s + n.y * n.y * a, -n.y))
+}
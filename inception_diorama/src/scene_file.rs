@@ -0,0 +1,1115 @@
+// src/scene_file.rs
+// Formato de escena en texto: una declaración por línea, `#` comenta
+// hasta el final de la línea. Ejemplo:
+//
+//   sky horizon 0.7 0.8 0.95 zenith 0.4 0.6 0.95
+//   sky time 15.5 turbidity 3 latitude 35      # cielo de Preetham con sol
+//   light directional dir 0.4 -0.7 0.3 color 1 0.98 0.95 intensity 1.8
+//   light point pos 0 0.3 3.5 color 1 0.8 0.6 intensity 3 range 8
+//   light spot pos 0 0.45 7.8 dir 0 -1 0.6 intensity 4 inner 25 outer 45
+//   light area corner -3 0.55 1.5 u 1.2 0 0 v 0 0 1 intensity 2 samples 3
+//   ambient color 0.35 0.4 0.5 intensity 0.4
+//   camera default eye 0 0 12 yaw 0 pitch 0 roll 0 fov 60
+//   material grass diffuse albedo 0.15 0.35 0.12 specular 0.02 reflectivity 0.02
+//   material glass dielectric ior 1.5 absorption 0.1 0.03 0.01 transparency 1
+//   texture bricks brick a 0.5 0.2 0.1 b 0.7 0.7 0.6 size 0.4 0.2 gap 0.02 # checker, noise, wood,
+//                                                   # brick, tile o marble; [scale S] [seed N] [uv]
+//   texture grass_block image textures/grass.png side textures/grass_side.png scale 2 wrap repeat
+//                                                   # PNG o PPM; `side` para los costados; repeat, clamp o mirror;
+//                                                   # `data FILE` en vez de `image`: valores sin sRGB (normales)
+//   material wall diffuse texture bricks            # la textura da el albedo
+//   material path diffuse bump stone_noise 0.02     # relieve: alturas (gris) por 0.02, o
+//   material rock diffuse normal rock_n 1           # `normal` con una textura `data` en espacio tangente
+//   box grass -18 -2 -18  18 -1.95 18              # min max
+//   obox roof -2.3 1.1 4  4.8 0.15 8.2 rot 0 0 15   # centro tamaño [rot x y z | quat w x y z]
+//   sphere metal 0 1 0  0.5                         # centro radio
+//   plane floor 0 -2 0  0 1 0                       # punto normal
+//   cylinder wood 5.3 -1.9 6.3  5.3 -1.2 6.3  0.3   # base tapa radio
+//   triangle roof -1 0 0  1 0 0  0 1 0              # vértices (antihorario)
+//   mesh props/rock.obj pos 3 -1.9 9 rot 0 40 0 scale 0.5 [material stone]
+//   prototype post                                  # objetos en coordenadas locales...
+//   box fence -0.08 0 -0.08  0.08 1.15 0.08
+//   end                                             # ...hasta acá
+//   instance post pos 2 -1.95 4 rot 0 15 0 scale 1
+//   vox props/pig.vox pos 3 -1.95 9 scale 0.0625      # modelo de MagicaVoxel, apoyado en pos [merge]
+//   voxels origin -64 -4 -64 dims 256 40 256 scale 0.5   # grilla de bloques... [merge: en cajas]
+//   block 1 grass                                   # id (1-255) y material
+//   terrain 1 2 3 seed 7 base 4 height 20 freq 0.02 # arriba, debajo, fondo
+//   fill 0 92 4 92  163 39 163                      # id (0 = aire), desde y hasta, inclusive
+//   end
+//   sdf                                             # campo de distancia, sumando en orden...
+//   capsule wood 0 0 0  0 3 0  0.2                  # extremos radio
+//   union 0.5 sphere foliage 0 3.5 0  1.4           # empalme de 0.5; sin número, seco
+//   subtract 0.1 roundbox foliage 0 5 0  1 1 1  0.2 # centro tamaño redondeo
+//   union torus rope 0 1 0  0.5 0.08                # centro, radios mayor y menor
+//   end                                             # ...hasta acá (puede ir en un prototipo)
+//   heightfield grass pos -18 -2 -18 size 36 36 height 4 noise 5 res 129 freq 0.1 valley 0 2 14 22 slope stone 25 40 high snow 3 4
+//                                                   # alturas de `noise` o de `image mapa.png`; `valley`: plano en un
+//                                                   # círculo; `slope` y `high`: otro material con la pendiente o la altura;
+//                                                   # `res` va de 2 a 2049
+//   csg                                             # sólido: la primera forma...
+//   box wall -4 -1.9 7.3  4 0.6 7.5
+//   subtract box wall -0.8 -2 7.2  0.8 0.1 7.6      # ...menos, más (union) o por (intersect)
+//   end                                             # cajas, esferas y cilindros
+//
+// Los parámetros con nombre son opcionales y pueden ir en cualquier orden.
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::path::Path;
+use crate::math::Vec3;
+use crate::aabb::Aabb;
+use crate::shape::{Object, Shape};
+use crate::sphere::Sphere;
+use crate::plane::Plane;
+use crate::cylinder::Cylinder;
+use crate::triangle::Triangle;
+use crate::camera::CameraPreset;
+use crate::light::Light;
+use crate::material::{Material, Kind, Relief};
+use std::sync::Arc;
+use crate::scene::{Scene, MeshImport, MergedVoxels};
+use crate::mesh::{Mesh, MeshTri};
+use crate::transform::{Transform, euler_quat};
+use crate::obox::OrientedBox;
+use crate::obj;
+use crate::vox;
+use crate::sky::Daylight;
+use crate::instance::{Instance, Prototype};
+use crate::voxel::{VoxelGrid, VoxelEdit, MAX_DIM};
+use crate::sdf::{Sdf, Step, Prim, Op};
+use crate::csg::{Csg, CsgOp};
+use crate::heightfield::{Heightfield, HeightSource, Layer, MAX_RES};
+use crate::texture::{Texture, Pattern, ImageMap, Wrap};
+use crate::image;
+
+#[derive(Debug)]
+pub struct SceneError {
+    pub line: usize, // 0 si el error no es de una línea concreta
+    pub msg: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 { write!(f, "{}", self.msg) } else { write!(f, "línea {}: {}", self.line, self.msg) }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+fn err<T>(msg: impl Into<String>) -> Result<T, SceneError> {
+    Err(SceneError { line: 0, msg: msg.into() })
+}
+
+// Cursor sobre las palabras de una línea
+struct Tokens<'a> { words: std::iter::Peekable<std::str::SplitWhitespace<'a>> }
+
+impl<'a> Tokens<'a> {
+    fn new(line: &'a str) -> Self { Self { words: line.split_whitespace().peekable() } }
+
+    fn word(&mut self, what: &str) -> Result<&'a str, SceneError> {
+        match self.words.next() { Some(w) => Ok(w), None => err(format!("falta {what}")) }
+    }
+
+    fn f32(&mut self, what: &str) -> Result<f32, SceneError> {
+        let w = self.word(what)?;
+        w.parse().or_else(|_| err(format!("{what}: número inválido '{w}'")))
+    }
+
+    fn u32(&mut self, what: &str) -> Result<u32, SceneError> {
+        let w = self.word(what)?;
+        w.parse().or_else(|_| err(format!("{what}: entero inválido '{w}'")))
+    }
+
+    fn vec3(&mut self, what: &str) -> Result<Vec3, SceneError> {
+        Ok(Vec3::new(self.f32(what)?, self.f32(what)?, self.f32(what)?))
+    }
+
+    fn done(&mut self) -> bool { self.words.peek().is_none() }
+
+    fn next_is_number(&mut self) -> bool { self.words.peek().is_some_and(|w| w.parse::<f32>().is_ok()) }
+}
+
+pub fn load(path: &Path) -> Result<Scene, SceneError> {
+    let src = std::fs::read_to_string(path)
+        .or_else(|e| err(format!("{}: {e}", path.display())))?;
+    parse(&src, path.parent().unwrap_or(Path::new(".")))
+}
+
+// `dir` es desde dónde se resuelven las rutas relativas (las de `mesh`)
+pub fn parse(src: &str, dir: &Path) -> Result<Scene, SceneError> {
+    let mut scene = Scene::new(Vec::new(), Vec::new());
+    let mut mat_ids: HashMap<String, usize> = HashMap::new();
+    let mut open = Vec::new();
+
+    for (ln, raw) in src.lines().enumerate() {
+        let line = raw.split('#').next().unwrap_or("");
+        let mut t = Tokens::new(line);
+        if t.done() { continue; }
+        parse_line(&mut t, &mut scene, &mut mat_ids, dir, &mut open)
+            .map_err(|e| SceneError { line: ln + 1, msg: e.msg })?;
+    }
+    match open.last() {
+        Some(Block::Prototype { name, .. }) => return err(format!("prototipo '{name}' sin `end`")),
+        Some(Block::Voxels { .. }) => return err("voxels sin `end`"),
+        Some(Block::Sdf(_)) => return err("sdf sin `end`"),
+        Some(Block::Csg(_)) => return err("csg sin `end`"),
+        None => {}
+    }
+    scene.rebuild();
+    Ok(scene)
+}
+
+// Un `prototype … end` o un `voxels`, `sdf` o `csg` hasta `end` que se está
+// leyendo. Los últimos pueden ir dentro de un prototipo.
+enum Block {
+    Prototype { name: String, first: usize }, // los objetos desde `first` son suyos
+    Voxels { grid: Box<VoxelGrid>, merge: bool },
+    Sdf(Vec<Step>),
+    Csg(Vec<(CsgOp, Object)>),
+}
+
+fn parse_line(t: &mut Tokens, scene: &mut Scene, mat_ids: &mut HashMap<String, usize>, dir: &Path,
+              open: &mut Vec<Block>) -> Result<(), SceneError> {
+    let key = t.word("declaración")?;
+    match open.last_mut() {
+        Some(Block::Voxels { grid, .. }) if key != "end" => return parse_voxel_line(key, t, grid, mat_ids),
+        Some(Block::Sdf(steps)) if key != "end" => return parse_sdf_line(key, t, steps, mat_ids),
+        Some(Block::Csg(steps)) if key != "end" => return parse_csg_line(key, t, steps, mat_ids),
+        _ => {}
+    }
+    if let Some(o) = parse_shape(key, t, mat_ids)? {
+        scene.objects.push(o);
+        return Ok(());
+    }
+    match key {
+        "sky" => {
+            while !t.done() {
+                match t.word("parámetro")? {
+                    "horizon" => scene.sky.horizon = t.vec3("horizon")?,
+                    "zenith"  => scene.sky.zenith = t.vec3("zenith")?,
+                    k @ ("time" | "turbidity" | "latitude" | "declination" | "sun" | "moon") => {
+                        let x = t.f32(k)?;
+                        let d = scene.sky.daylight.get_or_insert_with(Daylight::default);
+                        match k {
+                            "time"        => d.time = x.rem_euclid(24.0),
+                            "turbidity"   => d.turbidity = x.max(1.0),
+                            "latitude"    => d.latitude = x,
+                            "declination" => d.declination = x,
+                            "sun"         => d.sun = x,
+                            _             => d.moon = x,
+                        }
+                    }
+                    k => return err(format!("sky: parámetro desconocido '{k}'")),
+                }
+            }
+        }
+        "light" => {
+            let light = parse_light(t)?;
+            scene.lights.push(light);
+        }
+        "ambient" => {
+            while !t.done() {
+                match t.word("parámetro")? {
+                    "color"     => scene.ambient.color = t.vec3("color")?,
+                    "intensity" => scene.ambient.intensity = t.f32("intensity")?,
+                    k => return err(format!("ambient: parámetro desconocido '{k}'")),
+                }
+            }
+        }
+        "camera" => {
+            let mut c = CameraPreset { name: t.word("nombre de cámara")?.to_string(), ..CameraPreset::default() };
+            while !t.done() {
+                match t.word("parámetro")? {
+                    "eye"   => c.eye = t.vec3("eye")?,
+                    "yaw"   => c.yaw = t.f32("yaw")?,
+                    "pitch" => c.pitch = t.f32("pitch")?,
+                    "roll"  => c.roll = t.f32("roll")?,
+                    "fov"   => c.fov = t.f32("fov")?,
+                    k => return err(format!("camera: parámetro desconocido '{k}'")),
+                }
+            }
+            scene.cameras.push(c);
+        }
+        "material" => {
+            let name = t.word("nombre de material")?;
+            if mat_ids.contains_key(name) { return err(format!("material '{name}' repetido")); }
+            let mat = parse_material(t, scene)?;
+            mat_ids.insert(name.to_string(), scene.mats.len());
+            scene.mats.push(mat);
+            scene.mat_names.push(name.to_string());
+        }
+        "texture" => {
+            let name = t.word("nombre de textura")?;
+            if scene.texture_names.iter().any(|n| n == name) { return err(format!("textura '{name}' repetida")); }
+            let tex = parse_texture(t, dir)?;
+            scene.textures.push(tex);
+            scene.texture_names.push(name.to_string());
+        }
+        "mesh" => {
+            // sus objetos se registran por rango en `meshes`: no pueden ir a un prototipo
+            if !open.is_empty() { return err("mesh: no se puede usar dentro de un prototipo"); }
+            let path = t.word("archivo")?.to_string();
+            let mut transform = (Vec3::default(), Vec3::default(), Vec3::new(1.0, 1.0, 1.0));
+            let mut material = None;
+            while !t.done() {
+                match t.word("parámetro")? {
+                    "material" => {
+                        let name = t.word("material")?;
+                        if !mat_ids.contains_key(name) { return err(format!("material '{name}' no definido")); }
+                        material = Some(name.to_string());
+                    }
+                    k => if !placement(t, k, &mut transform)? { return err(format!("mesh: parámetro desconocido '{k}'")) },
+                }
+            }
+            import_mesh(scene, mat_ids, dir, MeshImport { path, transform, material, objects: 0..0, mats: 0..0 })?;
+        }
+        "vox" => {
+            let path = t.word("archivo")?.to_string();
+            let (mut pos, mut scale, mut merge) = (Vec3::default(), 1.0, false);
+            while !t.done() {
+                match t.word("parámetro")? {
+                    "pos"   => pos = t.vec3("pos")?,
+                    "scale" => scale = t.f32("scale")?,
+                    "merge" => merge = true,
+                    k => return err(format!("vox: parámetro desconocido '{k}'")),
+                }
+            }
+            // como `mesh`: las cajas se registran por rango y no pueden ir a un prototipo
+            if merge && !open.is_empty() { return err("vox: merge no se puede usar dentro de un prototipo"); }
+            let grid = import_vox(scene, mat_ids, dir, path, pos, scale)?;
+            push_voxels(scene, grid, merge);
+        }
+        "prototype" => {
+            if !open.is_empty() { return err("prototype: falta el `end` del bloque anterior"); }
+            let name = t.word("nombre de prototipo")?;
+            if scene.prototypes.iter().any(|p| p.name == name) { return err(format!("prototipo '{name}' repetido")); }
+            if !t.done() { return err("prototype: sobran valores"); }
+            open.push(Block::Prototype { name: name.to_string(), first: scene.objects.len() });
+        }
+        "voxels" => {
+            let (mut origin, mut dims, mut scale, mut merge) = (Vec3::default(), None, 1.0, false);
+            while !t.done() {
+                match t.word("parámetro")? {
+                    "origin" => origin = t.vec3("origin")?,
+                    "dims"   => {
+                        let d = t.vec3("dims")?;
+                        if d.x < 1.0 || d.y < 1.0 || d.z < 1.0 { return err("voxels: dims tiene que ser al menos 1"); }
+                        let d = [d.x as usize, d.y as usize, d.z as usize];
+                        if d.iter().any(|&n| n > MAX_DIM) {
+                            return err(format!("voxels: dims demasiado grande (hasta {MAX_DIM} por eje)"));
+                        }
+                        dims = Some(d);
+                    }
+                    "scale"  => scale = t.f32("scale")?,
+                    "merge"  => merge = true,
+                    k => return err(format!("voxels: parámetro desconocido '{k}'")),
+                }
+            }
+            let Some(dims) = dims else { return err("voxels: falta dims") };
+            if merge && !open.is_empty() { return err("voxels: merge no se puede usar dentro de un prototipo"); }
+            open.push(Block::Voxels { grid: Box::new(VoxelGrid::new(origin, dims, scale)), merge });
+        }
+        "heightfield" => {
+            let f = parse_heightfield(t, mat_ids, dir)?;
+            scene.objects.push(Object::Heightfield(Arc::new(f)));
+        }
+        "sdf" => {
+            if !t.done() { return err("sdf: sobran valores"); }
+            open.push(Block::Sdf(Vec::new()));
+        }
+        "csg" => {
+            if !t.done() { return err("csg: sobran valores"); }
+            open.push(Block::Csg(Vec::new()));
+        }
+        "end" => {
+            if !t.done() { return err("end: sobran valores"); }
+            match open.pop() {
+                Some(Block::Prototype { name, first }) => {
+                    if first == scene.objects.len() { return err(format!("prototipo '{name}' vacío")); }
+                    let objects = scene.objects.drain(first..).collect();
+                    scene.prototypes.push(Arc::new(Prototype::new(name, objects)));
+                }
+                Some(Block::Voxels { mut grid, merge }) => {
+                    grid.finish();
+                    push_voxels(scene, *grid, merge);
+                }
+                Some(Block::Sdf(steps)) => {
+                    if steps.is_empty() { return err("sdf vacío"); }
+                    scene.objects.push(Object::Sdf(Arc::new(Sdf::new(steps))));
+                }
+                Some(Block::Csg(steps)) => {
+                    if steps.is_empty() { return err("csg vacío"); }
+                    let Some(csg) = Csg::new(steps) else { return err("csg: la intersección no deja nada") };
+                    scene.objects.push(Object::Csg(Arc::new(csg)));
+                }
+                None => return err("end: no hay bloque abierto"),
+            }
+        }
+        "instance" => {
+            let name = t.word("prototipo")?;
+            let Some(proto) = scene.prototypes.iter().find(|p| p.name == name).cloned() else {
+                return err(format!("prototipo '{name}' no definido"));
+            };
+            let mut transform = (Vec3::default(), Vec3::default(), Vec3::new(1.0, 1.0, 1.0));
+            while !t.done() {
+                let k = t.word("parámetro")?;
+                if !placement(t, k, &mut transform)? { return err(format!("instance: parámetro desconocido '{k}'")); }
+            }
+            let (pos, rot, scale) = transform;
+            scene.objects.push(Object::Instance(Instance::new(proto, pos, rot, scale)));
+        }
+        k => return err(format!("declaración desconocida '{k}'")),
+    }
+    Ok(())
+}
+
+// Una grilla terminada: tal cual, o con `merge` hecha cajas por mallado goloso
+fn push_voxels(scene: &mut Scene, grid: VoxelGrid, merge: bool) {
+    if !merge {
+        scene.objects.push(Object::Voxels(Arc::new(grid)));
+        return;
+    }
+    let first = scene.objects.len();
+    scene.objects.extend(grid.greedy().into_iter().map(Object::Box));
+    scene.merged.push(MergedVoxels { blocks: grid.count(), objects: first..scene.objects.len(), grid });
+}
+
+// Lo que va entre `voxels` y `end`
+fn parse_voxel_line(key: &str, t: &mut Tokens, grid: &mut VoxelGrid, mat_ids: &HashMap<String, usize>) -> Result<(), SceneError> {
+    let block_id = |t: &mut Tokens, grid: &VoxelGrid, air: bool| -> Result<u8, SceneError> {
+        let w = t.word("id de bloque")?;
+        let id: u8 = w.parse().or_else(|_| err(format!("id de bloque inválido '{w}'")))?;
+        if id == 0 && !air { return err("el id 0 es el aire"); }
+        if id != 0 && grid.palette[id as usize].is_none() { return err(format!("bloque {id} sin definir")); }
+        Ok(id)
+    };
+    let cell = |t: &mut Tokens, what: &str| -> Result<[i32; 3], SceneError> {
+        let v = t.vec3(what)?;
+        Ok([v.x as i32, v.y as i32, v.z as i32])
+    };
+    match key {
+        "block" => {
+            let w = t.word("id de bloque")?;
+            let id: u8 = w.parse().or_else(|_| err(format!("id de bloque inválido '{w}'")))?;
+            if id == 0 { return err("el id 0 es el aire"); }
+            grid.palette[id as usize] = Some(mat_ref(t, mat_ids)?);
+        }
+        "fill" => {
+            let id = block_id(t, grid, true)?;
+            let (min, max) = (cell(t, "desde")?, cell(t, "hasta")?);
+            grid.apply(VoxelEdit::Fill { id, min, max });
+        }
+        "terrain" => {
+            let (top, under, deep) = (block_id(t, grid, false)?, block_id(t, grid, false)?, block_id(t, grid, false)?);
+            let (mut seed, mut base, mut height, mut freq) = (1, 4.0, 8.0, 0.05);
+            while !t.done() {
+                match t.word("parámetro")? {
+                    "seed"   => seed = t.u32("seed")?,
+                    "base"   => base = t.f32("base")?,
+                    "height" => height = t.f32("height")?,
+                    "freq"   => freq = t.f32("freq")?,
+                    k => return err(format!("terrain: parámetro desconocido '{k}'")),
+                }
+            }
+            grid.apply(VoxelEdit::Terrain { top, under, deep, seed, base, height, freq });
+        }
+        k => return err(format!("voxels: '{k}' no va dentro de un bloque de voxels")),
+    }
+    if !t.done() { return err(format!("{key}: sobran valores")); }
+    Ok(())
+}
+
+// `heightfield MAT pos … size SX SZ height H` y de dónde salen las alturas:
+// `noise SEMILLA [res N] [freq F]` o `image ARCHIVO` (gris, cuadrada)
+fn parse_heightfield(t: &mut Tokens, mat_ids: &HashMap<String, usize>, dir: &Path) -> Result<Heightfield, SceneError> {
+    let mat_id = mat_ref(t, mat_ids)?;
+    let (mut pos, mut size, mut height) = (Vec3::default(), [16.0, 16.0], 1.0);
+    let (mut source, mut valley, mut slope, mut high) = (None, None, None, None);
+    let layer = |t: &mut Tokens, what: &str| -> Result<Option<Layer>, SceneError> {
+        Ok(Some(Layer { mat_id: mat_ref(t, mat_ids)?, lo: t.f32(what)?, hi: t.f32(what)? }))
+    };
+    while !t.done() {
+        match t.word("parámetro")? {
+            "pos"    => pos = t.vec3("pos")?,
+            "size"   => size = [t.f32("size")?, t.f32("size")?],
+            "height" => height = t.f32("height")?,
+            "noise"  => {
+                let seed = t.u32("semilla")?;
+                let (mut res, mut freq) = (129, 0.1);
+                loop {
+                    match t.words.peek() {
+                        Some(&"res")  => { t.word("res")?; res = (t.u32("res")? as usize).clamp(2, MAX_RES); }
+                        Some(&"freq") => { t.word("freq")?; freq = t.f32("freq")?; }
+                        _ => break,
+                    }
+                }
+                source = Some(HeightSource::Noise { seed, res, freq });
+            }
+            "image"  => source = Some(HeightSource::Image(t.word("archivo")?.to_string())),
+            "valley" => valley = Some([t.f32("valley")?, t.f32("valley")?, t.f32("valley")?, t.f32("valley")?]),
+            "slope"  => slope = layer(t, "slope")?,
+            "high"   => high = layer(t, "high")?,
+            k => return err(format!("heightfield: parámetro desconocido '{k}'")),
+        }
+    }
+    let mut f = match source {
+        Some(HeightSource::Noise { seed, res, freq }) => Heightfield::noise(pos, size, height, seed, res, freq),
+        Some(HeightSource::Image(path)) => {
+            let img = image::load(&dir.join(&path)).or_else(|e| err(format!("heightfield: {path}: {e}")))?;
+            if img.w != img.h || img.w < 2 { return err(format!("heightfield: {path} tiene que ser cuadrada")); }
+            Heightfield::image(pos, size, height, path, &img)
+        }
+        None => return err("heightfield: falta noise o image"),
+    };
+    if let Some(v) = valley { f.set_valley(v); }
+    (f.mat_id, f.slope, f.high) = (mat_id, slope, high);
+    Ok(f)
+}
+
+// Las formas de una sola línea; None si `key` es otra cosa
+fn parse_shape(key: &str, t: &mut Tokens, mat_ids: &HashMap<String, usize>) -> Result<Option<Object>, SceneError> {
+    let o = match key {
+        "box" => {
+            let mat_id = mat_ref(t, mat_ids)?;
+            let min = t.vec3("min")?;
+            let max = t.vec3("max")?;
+            Object::Box(Aabb { min, max, mat_id })
+        }
+        "obox" => {
+            let mat_id = mat_ref(t, mat_ids)?;
+            let center = t.vec3("centro")?;
+            let size = t.vec3("tamaño")?;
+            let rot = match t.words.next() {
+                None => [1.0, 0.0, 0.0, 0.0],
+                Some("rot") => euler_quat(t.vec3("rot")?),
+                Some("quat") => [t.f32("quat")?, t.f32("quat")?, t.f32("quat")?, t.f32("quat")?],
+                Some(k) => return err(format!("obox: parámetro desconocido '{k}'")),
+            };
+            Object::OBox(OrientedBox::new(center, size, rot, mat_id))
+        }
+        "sphere" => {
+            let mat_id = mat_ref(t, mat_ids)?;
+            let center = t.vec3("centro")?;
+            let radius = t.f32("radio")?;
+            Object::Sphere(Sphere { center, radius, mat_id })
+        }
+        "plane" => {
+            let mat_id = mat_ref(t, mat_ids)?;
+            let point = t.vec3("punto")?;
+            let normal = t.vec3("normal")?;
+            Object::Plane(Plane { point, normal, mat_id })
+        }
+        "cylinder" => {
+            let mat_id = mat_ref(t, mat_ids)?;
+            let a = t.vec3("base")?;
+            let b = t.vec3("tapa")?;
+            let radius = t.f32("radio")?;
+            Object::Cylinder(Cylinder { a, b, radius, mat_id })
+        }
+        "triangle" => {
+            let mat_id = mat_ref(t, mat_ids)?;
+            let (a, b, c) = (t.vec3("vértice a")?, t.vec3("vértice b")?, t.vec3("vértice c")?);
+            Object::Triangle(Triangle { a, b, c, mat_id })
+        }
+        _ => return Ok(None),
+    };
+    if !t.done() { return err(format!("{key}: sobran valores")); }
+    Ok(Some(o))
+}
+
+// Lo que va entre `csg` y `end`: cada forma se suma a lo anterior, o con
+// `intersect` / `subtract` delante, se corta o se resta. Solo formas convexas y cerradas.
+fn parse_csg_line(key: &str, t: &mut Tokens, steps: &mut Vec<(CsgOp, Object)>, mat_ids: &HashMap<String, usize>) -> Result<(), SceneError> {
+    let (op, shape) = match key {
+        "union"     => (CsgOp::Union, t.word("forma")?),
+        "intersect" => (CsgOp::Intersect, t.word("forma")?),
+        "subtract"  => (CsgOp::Subtract, t.word("forma")?),
+        k => (CsgOp::Union, k),
+    };
+    if steps.is_empty() && op != CsgOp::Union { return err("csg: la primera forma no se combina con nada"); }
+    if !matches!(shape, "box" | "obox" | "sphere" | "cylinder") { return err(format!("csg: '{shape}' no es una forma cerrada y convexa")); }
+    if let Some(o) = parse_shape(shape, t, mat_ids)? { steps.push((op, o)); }
+    Ok(())
+}
+
+// Lo que va entre `sdf` y `end`: cada primitiva se suma a lo anterior, o
+// con `union K` / `subtract K` se suma o se resta con un empalme de ancho K
+fn parse_sdf_line(key: &str, t: &mut Tokens, steps: &mut Vec<Step>, mat_ids: &HashMap<String, usize>) -> Result<(), SceneError> {
+    let (op, smooth, prim) = match key {
+        "union" | "subtract" => {
+            let smooth = if t.next_is_number() { t.f32("empalme")?.max(0.0) } else { 0.0 };
+            (if key == "union" { Op::Union } else { Op::Subtract }, smooth, t.word("primitiva")?)
+        }
+        k => (Op::Union, 0.0, k),
+    };
+    if steps.is_empty() && (op == Op::Subtract || smooth > 0.0) { return err("sdf: la primera primitiva no se combina con nada"); }
+    let mat_id = mat_ref(t, mat_ids)?;
+    let prim = match prim {
+        "sphere"   => Prim::Sphere { center: t.vec3("centro")?, radius: radius(t, "sphere", "radio")? },
+        "roundbox" => {
+            let (center, half) = (t.vec3("centro")?, t.vec3("tamaño")? * 0.5);
+            if half.x <= 0.0 || half.y <= 0.0 || half.z <= 0.0 { return err("roundbox: tamaño tiene que ser positivo"); }
+            Prim::RoundBox { center, half, radius: t.f32("redondeo")?.clamp(0.0, half.x.min(half.y).min(half.z)) }
+        }
+        "torus"    => Prim::Torus { center: t.vec3("centro")?, major: radius(t, "torus", "radio mayor")?, minor: radius(t, "torus", "radio menor")? },
+        "capsule"  => Prim::Capsule { a: t.vec3("extremo a")?, b: t.vec3("extremo b")?, radius: radius(t, "capsule", "radio")? },
+        k => return err(format!("sdf: primitiva desconocida '{k}'")),
+    };
+    if !t.done() { return err(format!("{key}: sobran valores")); }
+    steps.push(Step { op, smooth, prim, mat_id });
+    Ok(())
+}
+
+// Un radio de primitiva sdf: cero vale, negativo no
+fn radius(t: &mut Tokens, prim: &str, what: &str) -> Result<f32, SceneError> {
+    let r = t.f32(what)?;
+    if r < 0.0 { return err(format!("{prim}: {what} no puede ser negativo")); }
+    Ok(r)
+}
+
+// `pos`, `rot` y `scale` de `mesh` e `instance`; false si `key` es otra cosa
+fn placement(t: &mut Tokens, key: &str, (pos, rot, scale): &mut (Vec3, Vec3, Vec3)) -> Result<bool, SceneError> {
+    match key {
+        "pos" => *pos = t.vec3("pos")?,
+        "rot" => *rot = t.vec3("rot")?,
+        "scale" => {
+            // un número escala parejo; tres, por eje
+            let s = t.f32("scale")?;
+            *scale = if t.next_is_number() { Vec3::new(s, t.f32("scale")?, t.f32("scale")?) } else { Vec3::new(s, s, s) };
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+// Lee el OBJ, agrega sus materiales como "archivo.material" (salvo que la
+// línea pida uno) y una malla con su BVH por cada material
+fn import_mesh(scene: &mut Scene, mat_ids: &mut HashMap<String, usize>, dir: &Path, mut imp: MeshImport) -> Result<(), SceneError> {
+    let file = dir.join(&imp.path);
+    let obj = obj::load(&file).or_else(|e| err(format!("mesh: {e}")))?;
+    let (pos, rot, scale) = imp.transform;
+    let xf = Transform::new(pos, rot, scale);
+
+    let first_mat = scene.mats.len();
+    let mut mat_for = |name: &Option<String>, scene: &mut Scene| -> usize {
+        if let Some(m) = &imp.material { return mat_ids[m]; }
+        // por la ruta, como los de `vox`: dos archivos con el mismo nombre no comparten materiales
+        let key = format!("{}.{}", imp.path, name.as_deref().unwrap_or("default"));
+        if let Some(&id) = mat_ids.get(&key) { return id; }
+        let mat = name.as_ref()
+            .and_then(|n| obj.materials.iter().find(|(mn, _)| mn == n))
+            .map(|(_, m)| *m)
+            .unwrap_or(Material { kind: Kind::Diffuse, albedo: Vec3::new(0.8, 0.8, 0.8), specular: 0.0, transparency: 0.0, reflectivity: 0.0, texture: None, relief: None });
+        mat_ids.insert(key.clone(), scene.mats.len());
+        scene.mats.push(mat);
+        scene.mat_names.push(key);
+        scene.mats.len() - 1
+    };
+
+    let first_obj = scene.objects.len();
+    for (name, faces) in &obj.groups {
+        let mat_id = mat_for(name, scene);
+        let tris = faces.iter().map(|f| {
+            let p = f.map(|c| xf.point(obj.positions[c.v]));
+            let normals = f.iter().all(|c| c.vn.is_some())
+                .then(|| f.map(|c| xf.normal(obj.normals[c.vn.unwrap()])));
+            let uvs = f.iter().all(|c| c.vt.is_some())
+                .then(|| f.map(|c| obj.texcoords[c.vt.unwrap()]));
+            MeshTri { tri: Triangle { a: p[0], b: p[1], c: p[2], mat_id }, normals, uvs }
+        }).collect();
+        scene.objects.push(Object::Mesh(Arc::new(Mesh::new(tris, mat_id))));
+    }
+    imp.objects = first_obj..scene.objects.len();
+    imp.mats = first_mat..scene.mats.len();
+    scene.meshes.push(imp);
+    Ok(())
+}
+
+// Arma una grilla con todos los modelos del .vox, apoyada en `pos` y
+// centrada en x y z. Cada color usado es un material "ruta.cN", con la
+// ruta como está en la escena: dos .vox con el mismo nombre en carpetas
+// distintas no comparten materiales.
+fn import_vox(scene: &mut Scene, mat_ids: &mut HashMap<String, usize>, dir: &Path, path: String, pos: Vec3, scale: f32) -> Result<VoxelGrid, SceneError> {
+    let file = dir.join(&path);
+    let vox = vox::load(&file).or_else(|e| err(format!("vox: {e}")))?;
+    if vox.voxels.is_empty() { return err(format!("vox: {path} no tiene bloques")); }
+
+    let (mut lo, mut hi) = ([i32::MAX; 3], [i32::MIN; 3]);
+    for (p, _) in &vox.voxels {
+        for i in 0..3 { lo[i] = lo[i].min(p[i]); hi[i] = hi[i].max(p[i]); }
+    }
+    // los bloques pueden quedar tan lejos entre sí como den las traslaciones del archivo
+    let dims = [0, 1, 2].map(|i| hi[i].checked_sub(lo[i]).and_then(|d| usize::try_from(d).ok()).map(|d| d + 1));
+    let dims = match dims {
+        [Some(x), Some(y), Some(z)] if x.max(y).max(z) <= MAX_DIM => [x, y, z],
+        _ => return err(format!("vox: {path} es demasiado grande (hasta {MAX_DIM} bloques por eje)")),
+    };
+    let origin = pos - Vec3::new(dims[0] as f32, 0.0, dims[2] as f32) * (scale * 0.5);
+    let mut grid = VoxelGrid::new(origin, dims, scale);
+    for &(p, color) in &vox.voxels {
+        if grid.palette[color as usize].is_none() {
+            let key = format!("{path}.c{color}");
+            let id = match mat_ids.get(&key) {
+                Some(&id) => id,
+                None => {
+                    mat_ids.insert(key.clone(), scene.mats.len());
+                    scene.mats.push(vox.material(color));
+                    scene.mat_names.push(key);
+                    scene.mats.len() - 1
+                }
+            };
+            grid.palette[color as usize] = Some(id);
+        }
+        grid.set((p[0] - lo[0]) as usize, (p[1] - lo[1]) as usize, (p[2] - lo[2]) as usize, color);
+    }
+    grid.finish();
+    grid.source = Some((path, pos));
+    Ok(grid)
+}
+
+fn mat_ref(t: &mut Tokens, mat_ids: &HashMap<String, usize>) -> Result<usize, SceneError> {
+    let name = t.word("material")?;
+    match mat_ids.get(name) { Some(&id) => Ok(id), None => err(format!("material '{name}' no definido")) }
+}
+
+fn tex_ref(t: &mut Tokens, scene: &Scene) -> Result<usize, SceneError> {
+    let name = t.word("textura")?;
+    match scene.texture_names.iter().position(|n| n == name) { Some(i) => Ok(i), None => err(format!("textura '{name}' no definida")) }
+}
+
+fn parse_material(t: &mut Tokens, scene: &Scene) -> Result<Material, SceneError> {
+    let mut kind = match t.word("tipo de material")? {
+        "diffuse"    => Kind::Diffuse,
+        "metal"      => Kind::Metal { rough: 0.0 },
+        "dielectric" => Kind::Dielectric { ior: 1.5, absorption: Vec3::default() },
+        "emissive"   => Kind::Emissive { intensity: 1.0 },
+        "plastic"    => Kind::Plastic { rough: 0.5 },
+        k => return err(format!("tipo de material desconocido '{k}'")),
+    };
+    let mut m = Material { kind, albedo: Vec3::new(0.8, 0.8, 0.8), specular: 0.0, transparency: 0.0, reflectivity: 0.0, texture: None, relief: None };
+    while !t.done() {
+        let key = t.word("parámetro")?;
+        match (key, &mut kind) {
+            ("albedo", _)       => m.albedo = t.vec3(key)?,
+            ("specular", _)     => m.specular = t.f32(key)?,
+            ("transparency", _) => m.transparency = t.f32(key)?,
+            ("reflectivity", _) => m.reflectivity = t.f32(key)?,
+            ("texture", _) => m.texture = Some(tex_ref(t, scene)?),
+            ("bump", _) => m.relief = Some(Relief::Bump { tex: tex_ref(t, scene)?, height: t.f32(key)? }),
+            ("normal", _) => {
+                let tex = tex_ref(t, scene)?;
+                let strength = if t.next_is_number() { t.f32(key)? } else { 1.0 };
+                m.relief = Some(Relief::Normal { tex, strength });
+            }
+            ("rough", Kind::Metal { rough } | Kind::Plastic { rough }) => *rough = t.f32(key)?,
+            ("ior", Kind::Dielectric { ior, .. }) => *ior = t.f32(key)?,
+            ("absorption", Kind::Dielectric { absorption, .. }) => *absorption = t.vec3(key)?,
+            ("intensity", Kind::Emissive { intensity }) => *intensity = t.f32(key)?,
+            _ => return err(format!("material: parámetro '{key}' no válido aquí")),
+        }
+    }
+    m.kind = kind;
+    Ok(m)
+}
+
+fn load_image(dir: &Path, path: &str) -> Result<image::Pixels, SceneError> {
+    let img = image::load(&dir.join(path)).or_else(|e| err(format!("texture: {path}: {e}")))?;
+    if img.w == 0 || img.h == 0 { return err(format!("texture: {path} está vacía")); }
+    Ok(img)
+}
+
+fn parse_texture(t: &mut Tokens, dir: &Path) -> Result<Texture, SceneError> {
+    let pattern = match t.word("tipo de textura")? {
+        "checker" => Pattern::Checker,
+        "noise"   => Pattern::Noise { octaves: 5 },
+        "wood"    => Pattern::Wood { turbulence: 1.0 },
+        "brick"   => Pattern::Brick { size: [0.4, 0.2], gap: 0.02, offset: 0.5 },
+        "tile"    => Pattern::Brick { size: [0.5, 0.5], gap: 0.02, offset: 0.0 },
+        "marble"  => Pattern::Marble { turbulence: 4.0 },
+        // `data`: valores tal cual, para normales o alturas
+        k @ ("image" | "data") => {
+            let path = t.word("archivo")?;
+            Pattern::Image(Box::new(ImageMap::new(path.to_string(), &load_image(dir, path)?, k == "data")))
+        }
+        k => return err(format!("tipo de textura desconocido '{k}'")),
+    };
+    let mut tex = Texture { pattern, a: Vec3::new(0.8, 0.8, 0.8), b: Vec3::new(0.2, 0.2, 0.2), scale: 1.0, seed: 0, uv: false };
+    while !t.done() {
+        let key = t.word("parámetro")?;
+        match (key, &mut tex.pattern) {
+            ("a" | "b" | "seed", Pattern::Image(_)) => return err(format!("texture: parámetro '{key}' no válido aquí")),
+            ("a", _)     => tex.a = t.vec3(key)?,
+            ("b", _)     => tex.b = t.vec3(key)?,
+            ("scale", _) => tex.scale = t.f32(key)?,
+            ("seed", _)  => tex.seed = t.u32(key)?,
+            ("uv", _)    => tex.uv = true,
+            ("octaves", Pattern::Noise { octaves }) => *octaves = t.u32(key)?.clamp(1, 12),
+            ("turbulence", Pattern::Wood { turbulence } | Pattern::Marble { turbulence }) => *turbulence = t.f32(key)?,
+            ("size", Pattern::Brick { size, .. }) => {
+                *size = [t.f32(key)?, t.f32(key)?];
+                if size[0] <= 0.0 || size[1] <= 0.0 { return err("size: tiene que ser positivo"); }
+            }
+            ("gap", Pattern::Brick { gap, .. }) => *gap = t.f32(key)?,
+            ("offset", Pattern::Brick { offset, .. }) => *offset = t.f32(key)?,
+            ("side", Pattern::Image(m)) => {
+                let path = t.word("archivo")?;
+                m.set_side(path.to_string(), &load_image(dir, path)?);
+            }
+            ("wrap", Pattern::Image(m)) => m.wrap = match t.word("wrap")? {
+                "repeat" => Wrap::Repeat,
+                "clamp"  => Wrap::Clamp,
+                "mirror" => Wrap::Mirror,
+                w => return err(format!("wrap: '{w}' no es repeat, clamp ni mirror")),
+            },
+            _ => return err(format!("texture: parámetro '{key}' no válido aquí")),
+        }
+    }
+    Ok(tex)
+}
+
+fn parse_light(t: &mut Tokens) -> Result<Light, SceneError> {
+    let white = Vec3::new(1.0, 1.0, 1.0);
+    let down = Vec3::new(0.0, -1.0, 0.0);
+    let mut light = match t.word("tipo de luz")? {
+        "directional" => Light::Directional { dir: down, color: white, intensity: 1.0 },
+        "point"       => Light::Point { pos: Vec3::default(), color: white, intensity: 1.0, range: 0.0 },
+        "spot"        => Light::Spot { pos: Vec3::default(), dir: down, color: white, intensity: 1.0, range: 0.0, inner: 20.0, outer: 30.0 },
+        "area"        => Light::Area { corner: Vec3::default(), u: Vec3::new(1.0, 0.0, 0.0), v: Vec3::new(0.0, 0.0, 1.0), color: white, intensity: 1.0, samples: 3 },
+        k => return err(format!("tipo de luz desconocido '{k}'")),
+    };
+    while !t.done() {
+        let key = t.word("parámetro")?;
+        match (key, &mut light) {
+            ("color", Light::Directional { color, .. } | Light::Point { color, .. }
+                | Light::Spot { color, .. } | Light::Area { color, .. }) => *color = t.vec3(key)?,
+            ("intensity", Light::Directional { intensity, .. } | Light::Point { intensity, .. }
+                | Light::Spot { intensity, .. } | Light::Area { intensity, .. }) => *intensity = t.f32(key)?,
+            ("dir", Light::Directional { dir, .. } | Light::Spot { dir, .. }) => *dir = t.vec3(key)?,
+            ("pos", Light::Point { pos, .. } | Light::Spot { pos, .. }) => *pos = t.vec3(key)?,
+            ("range", Light::Point { range, .. } | Light::Spot { range, .. }) => *range = t.f32(key)?,
+            ("inner", Light::Spot { inner, .. }) => *inner = t.f32(key)?,
+            ("outer", Light::Spot { outer, .. }) => *outer = t.f32(key)?,
+            ("corner", Light::Area { corner, .. }) => *corner = t.vec3(key)?,
+            ("u", Light::Area { u, .. }) => *u = t.vec3(key)?,
+            ("v", Light::Area { v, .. }) => *v = t.vec3(key)?,
+            ("samples", Light::Area { samples, .. }) => {
+                // por lado: salen samples² rayos de sombra
+                *samples = t.u32(key)?;
+                if !(1..=64).contains(samples) { return err("light: samples va de 1 a 64"); }
+            }
+            _ => return err(format!("light: parámetro '{key}' no válido aquí")),
+        }
+    }
+    Ok(light)
+}
+
+// ---------- Guardado ----------
+
+fn v3(v: Vec3) -> String { format!("{} {} {}", v.x, v.y, v.z) }
+
+pub fn save(scene: &Scene, path: &Path) -> std::io::Result<()> {
+    std::fs::write(path, to_string(scene))
+}
+
+pub fn to_string(scene: &Scene) -> String {
+    let mut s = String::new();
+    let _ = write!(s, "sky horizon {} zenith {}", v3(scene.sky.horizon), v3(scene.sky.zenith));
+    if let Some(d) = scene.sky.daylight {
+        let _ = write!(s, " time {} turbidity {} latitude {} declination {} sun {} moon {}",
+            d.time, d.turbidity, d.latitude, d.declination, d.sun, d.moon);
+    }
+    s.push('\n');
+    let _ = writeln!(s, "ambient color {} intensity {}", v3(scene.ambient.color), scene.ambient.intensity);
+    for c in &scene.cameras {
+        let _ = writeln!(s, "camera {} eye {} yaw {} pitch {} roll {} fov {}", c.name, v3(c.eye), c.yaw, c.pitch, c.roll, c.fov);
+    }
+    for l in &scene.lights {
+        let _ = writeln!(s, "light {}", light_line(l));
+    }
+    s.push('\n');
+    // lo que agregó una línea `mesh` (o una grilla con `merge`) se vuelve a escribir como esa línea
+    let imported_mat = |i: usize| scene.meshes.iter().any(|m| m.mats.contains(&i));
+    let imported_obj = |i: usize| scene.meshes.iter().any(|m| m.objects.contains(&i))
+        || scene.merged.iter().any(|m| m.objects.contains(&i));
+    for (tex, name) in scene.textures.iter().zip(&scene.texture_names) {
+        let _ = writeln!(s, "texture {name} {}", texture_line(tex));
+    }
+    for (i, (m, name)) in scene.mats.iter().zip(&scene.mat_names).enumerate() {
+        if imported_mat(i) { continue; }
+        let _ = write!(s, "material {name} {}", material_line(m));
+        if let Some(t) = m.texture { let _ = write!(s, " texture {}", scene.texture_names[t]); }
+        let _ = match m.relief {
+            Some(Relief::Bump { tex, height }) => write!(s, " bump {} {height}", scene.texture_names[tex]),
+            Some(Relief::Normal { tex, strength }) => write!(s, " normal {} {strength}", scene.texture_names[tex]),
+            None => Ok(()),
+        };
+        s.push('\n');
+    }
+    s.push('\n');
+    for m in &scene.meshes {
+        let (pos, rot, scale) = m.transform;
+        let _ = write!(s, "mesh {} pos {} rot {} scale {}", m.path, v3(pos), v3(rot), v3(scale));
+        if let Some(name) = &m.material { let _ = write!(s, " material {name}"); }
+        s.push('\n');
+    }
+    for m in &scene.merged {
+        let _ = writeln!(s, "{}", voxels_line(scene, &m.grid, true));
+    }
+    for p in &scene.prototypes {
+        let _ = writeln!(s, "prototype {}", p.name);
+        for o in &p.objects {
+            if let Some(line) = object_line(scene, o) { let _ = writeln!(s, "{line}"); }
+        }
+        s.push_str("end\n");
+    }
+    for (i, o) in scene.objects.iter().enumerate() {
+        if imported_obj(i) { continue; }
+        if let Some(line) = object_line(scene, o) { let _ = writeln!(s, "{line}"); }
+    }
+    s
+}
+
+fn object_line(scene: &Scene, o: &Object) -> Option<String> {
+    let mat = &scene.mat_names[o.mat_id()];
+    Some(match o {
+        Object::Box(c) => format!("box {mat} {}  {}", v3(c.min), v3(c.max)),
+        Object::OBox(c) => {
+            let [w, x, y, z] = c.rot;
+            format!("obox {mat} {}  {} quat {w} {x} {y} {z}", v3(c.center), v3(c.half * 2.0))
+        }
+        Object::Sphere(c) => format!("sphere {mat} {}  {}", v3(c.center), c.radius),
+        Object::Plane(c) => format!("plane {mat} {}  {}", v3(c.point), v3(c.normal)),
+        Object::Cylinder(c) => format!("cylinder {mat} {}  {}  {}", v3(c.a), v3(c.b), c.radius),
+        Object::Triangle(c) => format!("triangle {mat} {}  {}  {}", v3(c.a), v3(c.b), v3(c.c)),
+        Object::Mesh(_) => return None, // ya escrita como `mesh`
+        Object::Voxels(g) => voxels_line(scene, g, false),
+        Object::Sdf(s) => sdf_block(scene, s),
+        Object::Heightfield(f) => {
+            let mut out = format!("heightfield {mat} pos {} size {} {} height {}", v3(f.origin), f.size[0], f.size[1], f.height);
+            let _ = match &f.source {
+                HeightSource::Noise { seed, res, freq } => write!(out, " noise {seed} res {res} freq {freq}"),
+                HeightSource::Image(path) => write!(out, " image {path}"),
+            };
+            if let Some([x, z, r0, r1]) = f.valley { let _ = write!(out, " valley {x} {z} {r0} {r1}"); }
+            for (name, l) in [("slope", f.slope), ("high", f.high)] {
+                if let Some(l) = l { let _ = write!(out, " {name} {} {} {}", scene.mat_names[l.mat_id], l.lo, l.hi); }
+            }
+            out
+        }
+        Object::Csg(c) => {
+            let mut out = String::from("csg\n");
+            for (i, (op, o)) in c.steps.iter().enumerate() {
+                match op {
+                    _ if i == 0 => {}
+                    CsgOp::Union => out.push_str("union "),
+                    CsgOp::Intersect => out.push_str("intersect "),
+                    CsgOp::Subtract => out.push_str("subtract "),
+                }
+                let _ = writeln!(out, "{}", object_line(scene, o)?);
+            }
+            out.push_str("end");
+            out
+        }
+        Object::Instance(c) => {
+            let (pos, rot, scale) = c.placement;
+            format!("instance {} pos {} rot {} scale {}", c.proto.name, v3(pos), v3(rot), v3(scale))
+        }
+    })
+}
+
+// La línea `vox` de la que salió la grilla, o su bloque `voxels … end`
+fn voxels_line(scene: &Scene, g: &VoxelGrid, merge: bool) -> String {
+    let merge = if merge { " merge" } else { "" };
+    match &g.source {
+        Some((path, pos)) => format!("vox {path} pos {} scale {}{merge}", v3(*pos), g.scale),
+        None => voxels_block(scene, g, merge),
+    }
+}
+
+fn voxels_block(scene: &Scene, g: &VoxelGrid, merge: &str) -> String {
+    let mut out = format!("voxels origin {} dims {} {} {} scale {}{merge}\n", v3(g.origin), g.dims[0], g.dims[1], g.dims[2], g.scale);
+    for (id, m) in g.palette.iter().enumerate() {
+        if let Some(m) = m { let _ = writeln!(out, "block {id} {}", scene.mat_names[*m]); }
+    }
+    for e in &g.edits {
+        let _ = match *e {
+            VoxelEdit::Fill { id, min: [a, b, c], max: [x, y, z] } => writeln!(out, "fill {id} {a} {b} {c}  {x} {y} {z}"),
+            VoxelEdit::Terrain { top, under, deep, seed, base, height, freq } =>
+                writeln!(out, "terrain {top} {under} {deep} seed {seed} base {base} height {height} freq {freq}"),
+        };
+    }
+    out.push_str("end");
+    out
+}
+
+fn sdf_block(scene: &Scene, s: &Sdf) -> String {
+    let mut out = String::from("sdf\n");
+    for (i, st) in s.steps.iter().enumerate() {
+        match st.op {
+            _ if i == 0 => {}
+            Op::Union => { let _ = write!(out, "union {} ", st.smooth); }
+            Op::Subtract => { let _ = write!(out, "subtract {} ", st.smooth); }
+        }
+        let mat = &scene.mat_names[st.mat_id];
+        let _ = match st.prim {
+            Prim::Sphere { center, radius } => writeln!(out, "sphere {mat} {}  {radius}", v3(center)),
+            Prim::RoundBox { center, half, radius } => writeln!(out, "roundbox {mat} {}  {}  {radius}", v3(center), v3(half * 2.0)),
+            Prim::Torus { center, major, minor } => writeln!(out, "torus {mat} {}  {major} {minor}", v3(center)),
+            Prim::Capsule { a, b, radius } => writeln!(out, "capsule {mat} {}  {}  {radius}", v3(a), v3(b)),
+        };
+    }
+    out.push_str("end");
+    out
+}
+
+fn light_line(l: &Light) -> String {
+    match *l {
+        Light::Directional { dir, color, intensity } =>
+            format!("directional dir {} color {} intensity {intensity}", v3(dir), v3(color)),
+        Light::Point { pos, color, intensity, range } =>
+            format!("point pos {} color {} intensity {intensity} range {range}", v3(pos), v3(color)),
+        Light::Spot { pos, dir, color, intensity, range, inner, outer } =>
+            format!("spot pos {} dir {} color {} intensity {intensity} range {range} inner {inner} outer {outer}",
+                v3(pos), v3(dir), v3(color)),
+        Light::Area { corner, u, v, color, intensity, samples } =>
+            format!("area corner {} u {} v {} color {} intensity {intensity} samples {samples}",
+                v3(corner), v3(u), v3(v), v3(color)),
+    }
+}
+
+fn material_line(m: &Material) -> String {
+    let kind = match m.kind {
+        Kind::Diffuse => "diffuse".to_string(),
+        Kind::Metal { rough } => format!("metal rough {rough}"),
+        Kind::Dielectric { ior, absorption } => format!("dielectric ior {ior} absorption {}", v3(absorption)),
+        Kind::Emissive { intensity } => format!("emissive intensity {intensity}"),
+        Kind::Plastic { rough } => format!("plastic rough {rough}"),
+    };
+    format!("{kind} albedo {} specular {} transparency {} reflectivity {}",
+        v3(m.albedo), m.specular, m.transparency, m.reflectivity)
+}
+
+fn texture_line(t: &Texture) -> String {
+    let uv = if t.uv { " uv" } else { "" };
+    let pattern = match &t.pattern {
+        Pattern::Checker => "checker".to_string(),
+        Pattern::Noise { octaves } => format!("noise octaves {octaves}"),
+        Pattern::Wood { turbulence } => format!("wood turbulence {turbulence}"),
+        Pattern::Brick { size, gap, offset } => format!("brick size {} {} gap {gap} offset {offset}", size[0], size[1]),
+        Pattern::Marble { turbulence } => format!("marble turbulence {turbulence}"),
+        Pattern::Image(m) => {
+            let mut out = format!("{} {}", if m.data { "data" } else { "image" }, m.path);
+            if let Some(p) = &m.side_path { let _ = write!(out, " side {p}"); }
+            let wrap = match m.wrap { Wrap::Repeat => "repeat", Wrap::Clamp => "clamp", Wrap::Mirror => "mirror" };
+            return format!("{out} wrap {wrap} scale {}{uv}", t.scale);
+        }
+    };
+    format!("{pattern} a {} b {} scale {} seed {}{uv}", v3(t.a), v3(t.b), t.scale, t.seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir() -> &'static Path { Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/scenes")) }
+
+    // El error de una escena chica con un material `m`; tiene que fallar sin entrar en pánico
+    fn error(body: &str) -> String {
+        let src = format!("material m diffuse albedo 1 1 1\n{body}\n");
+        match parse(&src, dir()) {
+            Ok(_) => panic!("se aceptó:\n{body}"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn ok(body: &str) -> Scene {
+        let src = format!("material m diffuse albedo 1 1 1\n{body}\n");
+        parse(&src, dir()).unwrap_or_else(|e| panic!("{e}\n{body}"))
+    }
+
+    #[test]
+    fn malformed_lines() {
+        assert!(error("box m 0 0 0 1 1").contains("falta"));
+        assert!(error("box m 0 0 0 1 1 1 2").contains("sobran"));
+        assert!(error("box nada 0 0 0 1 1 1").contains("no definido"));
+        assert!(error("material m diffuse").contains("repetido"));
+        assert!(error("material q dielectric ior x").contains("número inválido"));
+        assert!(error("material q vidrio").contains("desconocido"));
+        assert!(error("cubo m 0 0 0 1 1 1").contains("desconocida"));
+        assert!(error("sun dir 0 -1 0").contains("desconocida"));
+        assert!(error("mesh no_existe.obj").contains("no_existe.obj"));
+        assert!(error("vox no_existe.vox").contains("no_existe.vox"));
+        assert!(error("sdf\nsphere m 0 0 0 1").contains("end"));
+        assert!(error("texture t image no_existe.png").contains("no_existe.png"));
+        // el número de línea es el del archivo
+        assert!(error("\n# nada\nbox m 0 0 0").starts_with("línea 4"));
+    }
+
+    // Guardar y volver a leer deja el mismo texto
+    #[test]
+    fn test_scene_round_trip() {
+        let first = to_string(&Scene::test_scene());
+        let again = parse(&first, dir()).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(to_string(&again), first);
+    }
+
+    #[test]
+    fn sdf_sizes_and_radii() {
+        assert!(error("sdf\nroundbox m 0 0 0  -1 1 1  0.2\nend").contains("tamaño tiene que ser positivo"));
+        assert!(error("sdf\nroundbox m 0 0 0  1 0 1  0.2\nend").contains("tamaño"));
+        assert!(error("sdf\nsphere m 0 0 0 -1\nend").contains("radio"));
+        assert!(error("sdf\ntorus m 0 0 0  1 -0.1\nend").contains("radio menor"));
+        assert!(error("sdf\ncapsule m 0 0 0  0 1 0  -0.2\nend").contains("radio"));
+        ok("sdf\nroundbox m 0 0 0  1 1 1  5\nunion 0.1 capsule m 0 0 0  0 1 0  0\nend");
+    }
+
+    #[test]
+    fn voxel_limits() {
+        assert!(error("voxels dims 1e9 1e9 1e9\nend").contains("demasiado grande"));
+        // un eje solo muy largo también: la tabla de chunks va entera
+        assert!(error("voxels dims 2000 1 1\nend").contains("demasiado grande"));
+        assert!(error("voxels dims 0 4 4\nend").contains("al menos 1"));
+        assert!(error("voxels dims 8 8 8\nblock 1 m\nterrain 1 1 1 seed 2.5\nend").contains("entero"));
+        // coordenadas enormes: se recortan a la grilla
+        ok("voxels dims 8 8 8\nblock 1 m\nfill 1  -3e9 0 0  3e9 2 2\nterrain 1 1 1 seed 3\nend");
+    }
+
+    #[test]
+    fn area_light_samples() {
+        for bad in ["2.7", "-3", "x"] {
+            assert!(error(&format!("light area corner 0 1 0 u 1 0 0 v 0 0 1 samples {bad}")).contains("entero"), "{bad}");
+        }
+        assert!(error("light area corner 0 1 0 u 1 0 0 v 0 0 1 samples 0").contains("samples"));
+        let s = ok("light area corner 0 1 0 u 1 0 0 v 0 0 1 samples 4");
+        assert_eq!(s.lights[0].samples(), 16);
+    }
+
+    // Los materiales de un .obj van con su ruta y se comparten entre sus copias
+    #[test]
+    fn mesh_materials_keyed_by_path() {
+        let s = ok("mesh props/rock.obj\nmesh props/rock.obj pos 3 0 0");
+        let rock = s.mat_names.iter().filter(|n| n.starts_with("props/rock.obj.")).count();
+        assert!(rock > 0);
+        assert_eq!(s.mat_names.len(), 1 + rock);
+    }
+
+    #[test]
+    fn heightfield_seed_and_res() {
+        for bad in ["noise 2.5", "noise -1", "noise 3 res 64.5"] {
+            assert!(error(&format!("heightfield m {bad}")).contains("entero"), "{bad}");
+        }
+        let s = ok("heightfield m noise 3 res 0");
+        let Some(Object::Heightfield(f)) = s.objects.last() else { panic!("sin terreno") };
+        assert!(matches!(f.source, HeightSource::Noise { seed: 3, res: 2, .. }));
+    }
+
+    #[test]
+    fn texture_seed_and_octaves() {
+        for bad in ["seed 1.5", "seed -2", "octaves 4.5"] {
+            assert!(error(&format!("texture t noise {bad}")).contains("entero"), "{bad}");
+        }
+        let s = ok("texture t noise seed 7 octaves 40");
+        assert!(matches!(s.textures[0].pattern, Pattern::Noise { octaves: 12 }));
+        assert_eq!(s.textures[0].seed, 7);
+    }
+}
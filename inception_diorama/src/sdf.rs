@@ -0,0 +1,172 @@
+// src/sdf.rs
+// Formas dadas por una función de distancia con signo: primitivas (esfera,
+// caja redondeada, toro, cápsula) que se van juntando en orden, cada una
+// sumada o restada a lo anterior, con borde suave si se pide. Se dibujan con
+// sphere tracing: se avanza por el rayo lo que dice el campo, que nunca se
+// pasa de la superficie más cercana.
+use crate::math::{Vec3, v};
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::shape::Shape;
+
+#[derive(Copy, Clone)]
+pub enum Prim {
+    Sphere { center: Vec3, radius: f32 },
+    RoundBox { center: Vec3, half: Vec3, radius: f32 }, // `half` incluye el redondeo
+    Torus { center: Vec3, major: f32, minor: f32 },     // acostado, alrededor de y
+    Capsule { a: Vec3, b: Vec3, radius: f32 },
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Op { Union, Subtract }
+
+// Una primitiva y cómo se combina con lo que hay; `smooth` es el ancho del
+// empalme (0, corte seco)
+#[derive(Copy, Clone)]
+pub struct Step { pub op: Op, pub smooth: f32, pub prim: Prim, pub mat_id: usize }
+
+pub struct Sdf {
+    pub steps: Vec<Step>, // el primero siempre es una unión
+    bounds: Aabb,
+}
+
+const EPS: f32 = 1e-4;
+const MAX_STEPS: usize = 256;
+
+fn vmax(a: Vec3, b: Vec3) -> Vec3 { Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)) }
+fn vmin(a: Vec3, b: Vec3) -> Vec3 { Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)) }
+
+impl Prim {
+    pub fn dist(&self, p: Vec3) -> f32 {
+        match *self {
+            Prim::Sphere { center, radius } => (p - center).len() - radius,
+            Prim::RoundBox { center, half, radius } => {
+                let d = p - center;
+                let q = Vec3::new(d.x.abs(), d.y.abs(), d.z.abs()) - half + v(radius);
+                vmax(q, v(0.0)).len() + q.x.max(q.y).max(q.z).min(0.0) - radius
+            }
+            Prim::Torus { center, major, minor } => {
+                let d = p - center;
+                let ring = (d.x * d.x + d.z * d.z).sqrt() - major;
+                (ring * ring + d.y * d.y).sqrt() - minor
+            }
+            Prim::Capsule { a, b, radius } => {
+                let (pa, ba) = (p - a, b - a);
+                let h = (pa.dot(ba) / ba.dot(ba).max(1e-12)).clamp(0.0, 1.0);
+                (pa - ba * h).len() - radius
+            }
+        }
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        match *self {
+            Prim::Sphere { center, radius } => (center - v(radius), center + v(radius)),
+            Prim::RoundBox { center, half, .. } => (center - half, center + half),
+            Prim::Torus { center, major, minor } => {
+                let e = Vec3::new(major + minor, minor, major + minor);
+                (center - e, center + e)
+            }
+            Prim::Capsule { a, b, radius } => (vmin(a, b) - v(radius), vmax(a, b) + v(radius)),
+        }
+    }
+}
+
+impl Sdf {
+    pub fn new(steps: Vec<Step>) -> Self {
+        // lo que se resta no agranda la caja; el empalme suave engorda hasta smooth/4
+        let (mut lo, mut hi, mut pad) = (v(f32::INFINITY), v(f32::NEG_INFINITY), 0.0f32);
+        for s in steps.iter().filter(|s| s.op == Op::Union) {
+            let (a, b) = s.prim.bounds();
+            (lo, hi, pad) = (vmin(lo, a), vmax(hi, b), pad.max(s.smooth * 0.25));
+        }
+        let bounds = Aabb { min: lo - v(pad + EPS), max: hi + v(pad + EPS), mat_id: steps[0].mat_id };
+        Self { steps, bounds }
+    }
+
+    // Distancia a la superficie y el material de la primitiva que la define ahí
+    pub fn dist(&self, p: Vec3) -> (f32, usize) {
+        let first = &self.steps[0];
+        let (mut d, mut m) = (first.prim.dist(p), first.mat_id);
+        for s in &self.steps[1..] {
+            let e = s.prim.dist(p);
+            let k = s.smooth;
+            match s.op {
+                // mínimo polinomial de Quilez: igual al mínimo lejos del empalme
+                Op::Union => {
+                    if e < d { m = s.mat_id; }
+                    d = if k > 0.0 {
+                        let h = (0.5 + 0.5 * (d - e) / k).clamp(0.0, 1.0);
+                        d + (e - d) * h - k * h * (1.0 - h)
+                    } else { d.min(e) };
+                }
+                // el hueco toma el material de lo que se resta
+                Op::Subtract => {
+                    if -e > d { m = s.mat_id; }
+                    d = if k > 0.0 {
+                        let h = (0.5 - 0.5 * (d + e) / k).clamp(0.0, 1.0);
+                        d + (-e - d) * h + k * h * (1.0 - h)
+                    } else { d.max(-e) };
+                }
+            }
+        }
+        (d, m)
+    }
+
+    // Gradiente por diferencias en un tetraedro: cuatro evaluaciones
+    fn normal(&self, p: Vec3) -> Vec3 {
+        let h = EPS * 2.0;
+        [Vec3::new(1.0, -1.0, -1.0), Vec3::new(-1.0, -1.0, 1.0), Vec3::new(-1.0, 1.0, -1.0), Vec3::new(1.0, 1.0, 1.0)]
+            .iter()
+            .fold(Vec3::default(), |n, &k| n + k * self.dist(p + k * h).0)
+            .norm()
+    }
+}
+
+impl Shape for Sdf {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        // solo se marcha dentro de la caja
+        let (mut t0, mut t1) = (tmin, tmax);
+        for (o, d, lo, hi) in [(ray.o.x, ray.d.x, self.bounds.min.x, self.bounds.max.x),
+                               (ray.o.y, ray.d.y, self.bounds.min.y, self.bounds.max.y),
+                               (ray.o.z, ray.d.z, self.bounds.min.z, self.bounds.max.z)] {
+            let inv = 1.0 / d;
+            let (a, b) = ((lo - o) * inv, (hi - o) * inv);
+            t0 = t0.max(a.min(b));
+            t1 = t1.min(a.max(b));
+            if t0 > t1 { return None; }
+        }
+
+        // la distancia está en unidades del mundo; d puede no ser unitario
+        let len = ray.d.len();
+        let mut t = t0;
+        // un rayo que sale de la superficie no la cuenta hasta haberse alejado;
+        // con |distancia| se encuentra también la salida desde adentro
+        let mut away = self.dist(ray.at(t)).0.abs() > EPS;
+        for _ in 0..MAX_STEPS {
+            let p = ray.at(t);
+            let (d, m) = self.dist(p);
+            if d.abs() < EPS && away {
+                let n = self.normal(p);
+                // uv: las dos coordenadas de la caja que más miran a la cara
+                let size = self.bounds.max - self.bounds.min;
+                let r = (p - self.bounds.min) * Vec3::new(1.0 / size.x, 1.0 / size.y, 1.0 / size.z);
+                let (ax, ay, az) = (n.x.abs(), n.y.abs(), n.z.abs());
+                let uv = if ax > ay && ax > az { [r.z, r.y] } else if ay > az { [r.x, r.z] } else { [r.x, r.y] };
+                return Some(Hit { t, p, n, mat_id: m, uv });
+            }
+            if d.abs() > EPS * 2.0 { away = true; }
+            t += d.abs().max(EPS) / len;
+            if t > t1 { return None; }
+        }
+        None
+    }
+
+    fn bounds(&self) -> Option<Aabb> { Some(self.bounds) }
+
+    fn mat_id(&self) -> usize { self.steps[0].mat_id }
+
+    // Como las instancias y los bloques: no se muestrea como luz
+    fn area(&self) -> f32 { 0.0 }
+
+    fn sample_surface(&self, _u: Vec3) -> (Vec3, Vec3) { (self.bounds.min, Vec3::new(0.0, 1.0, 0.0)) }
+}
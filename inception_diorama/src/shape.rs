@@ -0,0 +1,73 @@
+// src/shape.rs
+// Lo que se puede intersecar. Cada forma sabe su caja envolvente (para el
+// BVH) y cómo muestrearse por área (para las luces emisivas del path tracer).
+use std::sync::Arc;
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::sphere::Sphere;
+use crate::plane::Plane;
+use crate::cylinder::Cylinder;
+use crate::triangle::Triangle;
+use crate::mesh::Mesh;
+use crate::instance::Instance;
+use crate::obox::OrientedBox;
+use crate::voxel::VoxelGrid;
+use crate::sdf::Sdf;
+use crate::csg::Csg;
+use crate::heightfield::Heightfield;
+
+pub trait Shape {
+    // Impacto en (tmin, tmax) con normal hacia afuera y uv en [0,1]
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit>;
+    // None para formas infinitas: el BVH las prueba aparte
+    fn bounds(&self) -> Option<Aabb>;
+    fn mat_id(&self) -> usize;
+    fn area(&self) -> f32;
+    // Punto uniforme (por área) sobre la superficie, con su normal; u en [0,1)^3
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3);
+}
+
+// Las formas que puede tener una escena
+#[derive(Clone)]
+pub enum Object {
+    Box(Aabb),
+    OBox(OrientedBox),
+    Sphere(Sphere),
+    Plane(Plane),
+    Cylinder(Cylinder),
+    Triangle(Triangle),
+    Mesh(Arc<Mesh>), // compartida: varias copias pueden usar la misma malla
+    Instance(Instance), // un prototipo colocado con su transformación
+    Voxels(Arc<VoxelGrid>),
+    Sdf(Arc<Sdf>),
+    Csg(Arc<Csg>),
+    Heightfield(Arc<Heightfield>),
+}
+
+impl Object {
+    fn shape(&self) -> &dyn Shape {
+        match self {
+            Object::Box(s) => s,
+            Object::OBox(s) => s,
+            Object::Sphere(s) => s,
+            Object::Plane(s) => s,
+            Object::Cylinder(s) => s,
+            Object::Triangle(s) => s,
+            Object::Mesh(s) => s.as_ref(),
+            Object::Instance(s) => s,
+            Object::Voxels(s) => s.as_ref(),
+            Object::Sdf(s) => s.as_ref(),
+            Object::Csg(s) => s.as_ref(),
+            Object::Heightfield(s) => s.as_ref(),
+        }
+    }
+}
+
+impl Shape for Object {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> { self.shape().hit(ray, tmin, tmax) }
+    fn bounds(&self) -> Option<Aabb> { self.shape().bounds() }
+    fn mat_id(&self) -> usize { self.shape().mat_id() }
+    fn area(&self) -> f32 { self.shape().area() }
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3) { self.shape().sample_surface(u) }
+}
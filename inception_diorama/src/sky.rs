@@ -0,0 +1,197 @@
+// src/sky.rs
+// Cielo: un gradiente fijo de dos colores o, si hay hora del día, el modelo
+// analítico de Preetham (1999) con el sol en su lugar, y de noche un cielo
+// oscuro con estrellas y luna llena opuesta al sol. El sol (o la luna) que
+// ilumina la escena sale de acá, así las sombras coinciden con el cielo.
+use std::f32::consts::PI;
+use crate::math::{Vec3, v, lerp};
+use crate::light::Light;
+
+// Parámetros del ciclo día/noche
+#[derive(Copy, Clone)]
+pub struct Daylight {
+    pub time: f32,        // hora local, 0..24
+    pub turbidity: f32,   // 2 = muy claro, 10 = brumoso
+    pub latitude: f32,    // grados; el sur queda hacia +z
+    pub declination: f32, // grados; 0 = equinoccio, ±23.4 = solsticios
+    pub sun: f32,         // irradiancia del sol alto
+    pub moon: f32,        // irradiancia de la luna alta
+}
+
+impl Default for Daylight {
+    fn default() -> Self {
+        Self { time: 12.0, turbidity: 3.0, latitude: 35.0, declination: 0.0, sun: 1.8, moon: 0.12 }
+    }
+}
+
+// Lo que no depende de la dirección de vista, calculado al cambiar la hora
+#[derive(Copy, Clone)]
+struct SkyState {
+    sun_dir: Vec3,        // hacia el sol
+    perez: [[f32; 5]; 3], // coeficientes A..E para Y, x, y
+    zenith: [f32; 3],     // Y, x, y en el cenit dividido por F(0, θs)
+    day: f32,             // 1 de día, 0 de noche, suave en el crepúsculo
+}
+
+#[derive(Copy, Clone)]
+pub struct Sky {
+    // gradiente fijo, cuando `daylight` es None
+    pub horizon: Vec3,
+    pub zenith: Vec3,
+    pub daylight: Option<Daylight>,
+    state: Option<SkyState>,
+}
+
+impl Default for Sky {
+    fn default() -> Self {
+        Self { horizon: Vec3::new(0.70, 0.80, 0.95), zenith: Vec3::new(0.40, 0.60, 0.95), daylight: None, state: None }
+    }
+}
+
+const NIGHT_HORIZON: Vec3 = Vec3 { x: 0.012, y: 0.016, z: 0.030 };
+const NIGHT_ZENITH: Vec3 = Vec3 { x: 0.003, y: 0.005, z: 0.014 };
+const MOON_COLOR: Vec3 = Vec3 { x: 0.75, y: 0.82, z: 1.0 };
+const Y_SCALE: f32 = 0.08; // kcd/m² de Preetham a la escala de la escena
+const DISC: f32 = 0.99995; // coseno del radio aparente del sol y la luna (≈0.6°)
+
+fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Dirección hacia el sol para la hora, latitud y declinación dadas
+// (este = +x, norte = -z, arriba = +y)
+fn sun_direction(d: &Daylight) -> Vec3 {
+    let h = (d.time - 12.0) * 15.0f32.to_radians(); // ángulo horario
+    let (phi, delta) = (d.latitude.to_radians(), d.declination.to_radians());
+    let east = -delta.cos() * h.sin();
+    let north = delta.sin() * phi.cos() - delta.cos() * phi.sin() * h.cos();
+    let up = delta.sin() * phi.sin() + delta.cos() * phi.cos() * h.cos();
+    Vec3::new(east, up, -north).norm()
+}
+
+// Función de distribución de Perez
+fn perez(c: &[f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    let [a, b, cc, d, e] = *c;
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + cc * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+fn xyy_to_rgb(yl: f32, x: f32, y: f32) -> Vec3 {
+    let xx = x / y * yl;
+    let zz = (1.0 - x - y) / y * yl;
+    Vec3::new(
+        3.2406 * xx - 1.5372 * yl - 0.4986 * zz,
+        -0.9689 * xx + 1.8758 * yl + 0.0415 * zz,
+        0.0557 * xx - 0.2040 * yl + 1.0570 * zz,
+    )
+}
+
+// Transmitancia de la atmósfera para el sol a `sin_el` de altura, relativa
+// a tenerlo en el cenit (masa de aire de Kasten-Young)
+fn transmittance(sin_el: f32, turbidity: f32) -> Vec3 {
+    let el = sin_el.clamp(-1.0, 1.0).asin().to_degrees().max(0.0);
+    let m = 1.0 / (sin_el.max(0.0) + 0.50572 * (el + 6.07995).powf(-1.6364));
+    let ext = Vec3::new(0.06, 0.12, 0.28) + v(0.03 * turbidity);
+    let k = m - 1.0;
+    Vec3::new((-ext.x * k).exp(), (-ext.y * k).exp(), (-ext.z * k).exp())
+}
+
+// Estrellas fijas: unas pocas celdas de una grilla de direcciones
+fn stars(d: Vec3) -> f32 {
+    if d.y <= 0.0 { return 0.0; }
+    let (i, j, k) = ((d.x * 400.0).floor() as i32, (d.y * 400.0).floor() as i32, (d.z * 400.0).floor() as i32);
+    let mut h = (i as u32).wrapping_mul(73856093) ^ (j as u32).wrapping_mul(19349663) ^ (k as u32).wrapping_mul(83492791);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    if h.is_multiple_of(1000) { 0.2 + (h >> 20) as f32 / 4096.0 * 0.6 } else { 0.0 }
+}
+
+impl Sky {
+    // Recalcula lo que depende de la hora; llamar después de tocar `daylight`
+    pub fn update(&mut self) {
+        self.state = self.daylight.map(|d| {
+            let sun_dir = sun_direction(&d);
+            let t = d.turbidity;
+            let coef = [
+                [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+                [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+                [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+            ];
+            // el modelo no vale con el sol bajo el horizonte: se lo deja apoyado
+            let ts = sun_dir.y.clamp(0.0, 1.0).acos().min(PI / 2.0 - 0.02);
+            let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * ts);
+            let yz = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+            let (t2, ts2, ts3) = (t * t, ts * ts, ts * ts * ts);
+            let xz = t2 * (0.00166 * ts3 - 0.00375 * ts2 + 0.00209 * ts)
+                + t * (-0.02903 * ts3 + 0.06377 * ts2 - 0.03202 * ts + 0.00394)
+                + (0.11693 * ts3 - 0.21196 * ts2 + 0.06052 * ts + 0.25886);
+            let yzc = t2 * (0.00275 * ts3 - 0.00610 * ts2 + 0.00317 * ts)
+                + t * (-0.04214 * ts3 + 0.08970 * ts2 - 0.04153 * ts + 0.00516)
+                + (0.15346 * ts3 - 0.26756 * ts2 + 0.06670 * ts + 0.26688);
+            let zenith = [
+                yz / perez(&coef[0], 1.0, ts),
+                xz / perez(&coef[1], 1.0, ts),
+                yzc / perez(&coef[2], 1.0, ts),
+            ];
+            SkyState { sun_dir, perez: coef, zenith, day: smoothstep(-0.10, 0.05, sun_dir.y) }
+        });
+    }
+
+    // Mueve la hora (se da la vuelta a las 24)
+    pub fn set_time(&mut self, time: f32) {
+        if let Some(d) = &mut self.daylight {
+            d.time = time.rem_euclid(24.0);
+            self.update();
+        }
+    }
+
+    pub fn time(&self) -> Option<f32> { self.daylight.map(|d| d.time) }
+
+    // Radiancia del cielo en la dirección `d`
+    pub fn radiance(&self, d: Vec3) -> Vec3 {
+        let (Some(s), Some(dl)) = (self.state, self.daylight) else {
+            let t = (d.y * 0.5 + 0.5).clamp(0.0, 1.0);
+            return lerp(self.horizon, self.zenith, t.powf(0.7));
+        };
+        let up = d.y.max(0.0);
+        let mut col = v(0.0);
+        if s.day > 0.0 {
+            let cos_theta = up.max(0.01);
+            let cos_gamma = d.dot(s.sun_dir).clamp(-1.0, 1.0);
+            let gamma = cos_gamma.acos();
+            let yl = s.zenith[0] * perez(&s.perez[0], cos_theta, gamma);
+            let x = s.zenith[1] * perez(&s.perez[1], cos_theta, gamma);
+            let y = s.zenith[2] * perez(&s.perez[2], cos_theta, gamma);
+            let mut day = xyy_to_rgb(yl * Y_SCALE, x, y);
+            day = Vec3::new(day.x.max(0.0), day.y.max(0.0), day.z.max(0.0));
+            if cos_gamma > DISC && d.y > 0.0 {
+                day = day + transmittance(s.sun_dir.y, dl.turbidity) * 8.0;
+            }
+            col = day * s.day;
+        }
+        if s.day < 1.0 {
+            let mut night = lerp(NIGHT_HORIZON, NIGHT_ZENITH, up.powf(0.5)) + v(stars(d));
+            if d.dot(-s.sun_dir) > DISC && d.y > 0.0 { night = night + MOON_COLOR * 2.0; }
+            col = col + night * (1.0 - s.day);
+        }
+        col
+    }
+
+    // El sol de día y la luna de noche, como luz direccional
+    pub fn light(&self) -> Option<Light> {
+        let (s, dl) = (self.state?, self.daylight?);
+        if s.sun_dir.y > 0.0 {
+            let color = transmittance(s.sun_dir.y, dl.turbidity) * smoothstep(0.0, 0.035, s.sun_dir.y);
+            Some(Light::Directional { dir: -s.sun_dir, color, intensity: dl.sun })
+        } else {
+            let color = MOON_COLOR * smoothstep(0.0, 0.1, -s.sun_dir.y);
+            Some(Light::Directional { dir: s.sun_dir, color, intensity: dl.moon })
+        }
+    }
+
+    // Cuánto queda de la luz ambiente fija (la del modo Whitted) a esta hora
+    pub fn ambient_scale(&self) -> f32 {
+        match self.state { Some(s) => 0.08 + 0.92 * s.day, None => 1.0 }
+    }
+}
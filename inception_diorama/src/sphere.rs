@@ -0,0 +1,51 @@
+// src/sphere.rs
+use std::f32::consts::PI;
+use crate::math::{Vec3, v};
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::shape::Shape;
+
+#[derive(Copy, Clone)]
+pub struct Sphere { pub center: Vec3, pub radius: f32, pub mat_id: usize }
+
+// uv esférica de una normal: u da la vuelta alrededor de y, v va de polo a polo
+fn sphere_uv(n: Vec3) -> [f32; 2] {
+    [0.5 + n.z.atan2(n.x) / (2.0 * PI), 0.5 + n.y.clamp(-1.0, 1.0).asin() / PI]
+}
+
+impl Shape for Sphere {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        let oc = ray.o - self.center;
+        let a = ray.d.dot(ray.d);
+        let half_b = oc.dot(ray.d);
+        let c = oc.dot(oc) - self.radius * self.radius;
+        let disc = half_b * half_b - a * c;
+        if disc < 0.0 { return None; }
+        let sq = disc.sqrt();
+        // la raíz cercana, o la lejana si el origen está adentro
+        let mut t = (-half_b - sq) / a;
+        if t <= tmin || t >= tmax {
+            t = (-half_b + sq) / a;
+            if t <= tmin || t >= tmax { return None; }
+        }
+        let p = ray.at(t);
+        let n = (p - self.center) / self.radius;
+        Some(Hit { t, p, n, mat_id: self.mat_id, uv: sphere_uv(n) })
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb { min: self.center - v(self.radius), max: self.center + v(self.radius), mat_id: self.mat_id })
+    }
+
+    fn mat_id(&self) -> usize { self.mat_id }
+
+    fn area(&self) -> f32 { 4.0 * PI * self.radius * self.radius }
+
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3) {
+        let z = 1.0 - 2.0 * u.x;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.y;
+        let n = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+        (self.center + n * self.radius, n)
+    }
+}
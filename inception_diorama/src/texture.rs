@@ -0,0 +1,211 @@
+// src/texture.rs
+// Texturas: dan el albedo de un material punto a punto. Las procedurales
+// mezclan dos colores `a` y `b`; las de imagen se leen con filtro bilineal
+// entre los dos niveles de mipmap que corresponden al ancho del cono del
+// rayo. Se evalúan en coordenadas de mundo (el patrón sigue de un objeto al
+// de al lado; las imágenes, proyectadas sobre la cara que más mira a la
+// normal) o, con `uv`, en la del impacto. Una textura también puede dar
+// relieve: alturas (su gris) o un mapa de normales en el espacio tangente.
+use std::f32::consts::PI;
+use crate::math::{Vec3, v, lerp};
+use crate::aabb::Hit;
+use crate::noise::{fbm3, value2};
+use crate::image::Pixels;
+use crate::tonemap::srgb_to_linear;
+
+// Qué pasa fuera de [0,1]
+#[derive(Copy, Clone, PartialEq)]
+pub enum Wrap { Repeat, Clamp, Mirror }
+
+// Una imagen pasada a lineal y sus reducciones a la mitad (promedio de
+// 2×2), hasta 1×1
+pub struct MipMap { levels: Vec<(usize, usize, Vec<Vec3>)> }
+
+// Una textura de imagen: la de arriba y abajo y, si se da, otra para los
+// costados (como los bloques de pasto). Las rutas quedan para guardarla.
+// `data`: los valores se usan tal cual (normales, alturas), sin sacar el sRGB.
+pub struct ImageMap {
+    pub path: String,
+    pub side_path: Option<String>,
+    pub wrap: Wrap,
+    pub data: bool,
+    top: MipMap,
+    side: Option<MipMap>,
+}
+
+pub enum Pattern {
+    Checker,
+    Noise { octaves: u32 },
+    Wood { turbulence: f32 },                        // anillos alrededor del eje y
+    Brick { size: [f32; 2], gap: f32, offset: f32 }, // `a` el ladrillo, `b` la junta
+    Marble { turbulence: f32 },                      // vetas de `b` sobre `a`
+    Image(Box<ImageMap>),
+}
+
+pub struct Texture {
+    pub pattern: Pattern,
+    pub a: Vec3,
+    pub b: Vec3,
+    pub scale: f32, // las coordenadas se multiplican por esto (en imágenes: repeticiones por unidad)
+    pub seed: u32,
+    pub uv: bool,
+}
+
+// Las dos coordenadas de la cara que más mira hacia `n`
+fn planar(q: Vec3, n: Vec3) -> (f32, f32) {
+    let (ax, ay, az) = (n.x.abs(), n.y.abs(), n.z.abs());
+    if ax > ay && ax > az { (q.z, q.y) } else if ay > az { (q.x, q.z) } else { (q.x, q.y) }
+}
+
+fn wrap(i: i64, n: usize, mode: Wrap) -> usize {
+    let n = n as i64;
+    (match mode {
+        Wrap::Repeat => i.rem_euclid(n),
+        Wrap::Clamp => i.clamp(0, n - 1),
+        Wrap::Mirror => { let m = i.rem_euclid(2 * n); if m < n { m } else { 2 * n - 1 - m } }
+    }) as usize
+}
+
+impl MipMap {
+    // Los colores de la imagen vienen en sRGB, salvo que sea de datos
+    pub fn new(px: &Pixels, srgb: bool) -> Self {
+        let rgb: Vec<Vec3> = (0..px.w * px.h).map(|k| {
+            let i = k * px.channels;
+            let c = if px.channels >= 3 { Vec3::new(px.data[i], px.data[i + 1], px.data[i + 2]) } else { v(px.data[i]) };
+            if !srgb { return c; }
+            Vec3::new(srgb_to_linear(c.x), srgb_to_linear(c.y), srgb_to_linear(c.z))
+        }).collect();
+        let mut levels = vec![(px.w, px.h, rgb)];
+        while let Some((w, h, prev)) = levels.last().filter(|l| l.0 > 1 || l.1 > 1) {
+            let (w2, h2) = (w.div_ceil(2), h.div_ceil(2));
+            // en los bordes impares se repite la última fila o columna
+            let at = |x: usize, y: usize| prev[y.min(h - 1) * w + x.min(w - 1)];
+            let next = (0..w2 * h2).map(|k| {
+                let (x, y) = (k % w2 * 2, k / w2 * 2);
+                (at(x, y) + at(x + 1, y) + at(x, y + 1) + at(x + 1, y + 1)) * 0.25
+            }).collect();
+            levels.push((w2, h2, next));
+        }
+        Self { levels }
+    }
+
+    // Bilineal en el nivel `l`; (s, t) con t hacia arriba, como la uv
+    fn bilinear(&self, l: usize, s: f32, t: f32, mode: Wrap) -> Vec3 {
+        let (w, h, px) = &self.levels[l];
+        let (x, y) = (s * *w as f32 - 0.5, (1.0 - t) * *h as f32 - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let at = |i: i64, j: i64| px[wrap(j, *h, mode) * w + wrap(i, *w, mode)];
+        let (i, j) = (x0 as i64, y0 as i64);
+        lerp(lerp(at(i, j), at(i + 1, j), fx), lerp(at(i, j + 1), at(i + 1, j + 1), fx), fy)
+    }
+
+    // `texels`: cuántos píxeles de la imagen entera cubre el cono
+    fn sample(&self, s: f32, t: f32, texels: f32, mode: Wrap) -> Vec3 {
+        let top = (self.levels.len() - 1) as f32;
+        let lod = texels.max(1.0).log2().min(top);
+        let l = lod.floor() as usize;
+        let a = self.bilinear(l, s, t, mode);
+        if lod == l as f32 { return a; }
+        lerp(a, self.bilinear(l + 1, s, t, mode), lod - l as f32)
+    }
+}
+
+impl ImageMap {
+    pub fn new(path: String, top: &Pixels, data: bool) -> Self {
+        Self { path, side_path: None, wrap: Wrap::Repeat, data, top: MipMap::new(top, !data), side: None }
+    }
+
+    pub fn set_side(&mut self, path: String, px: &Pixels) {
+        self.side_path = Some(path);
+        self.side = Some(MipMap::new(px, !self.data));
+    }
+}
+
+// Coordenadas de imagen sobre la cara que más mira hacia `n`, derechas
+// vistas desde afuera
+fn face_st(q: Vec3, n: Vec3) -> (f32, f32, bool) {
+    let (ax, ay, az) = (n.x.abs(), n.y.abs(), n.z.abs());
+    if ay >= ax && ay >= az { (q.x, -q.z * n.y.signum(), false) }
+    else if ax > az { (-q.z * n.x.signum(), q.y, true) }
+    else { (q.x * n.z.signum(), q.y, true) }
+}
+
+// Hacia dónde crecen s y t de `face_st`, enderezados contra `n`; con `uv`
+// se usa el mismo marco, que es una aproximación
+pub fn tangents(n: Vec3) -> (Vec3, Vec3) {
+    let (ax, ay, az) = (n.x.abs(), n.y.abs(), n.z.abs());
+    let t = if ay >= ax && ay >= az { Vec3::new(1.0, 0.0, 0.0) }
+        else if ax > az { Vec3::new(0.0, 0.0, -n.x.signum()) }
+        else { Vec3::new(n.z.signum(), 0.0, 0.0) };
+    let t = (t - n * n.dot(t)).norm();
+    (t, n.cross(t))
+}
+
+impl Texture {
+    // `footprint`: ancho del cono del rayo en el impacto, en unidades de mundo
+    // (con `uv` se supone que una unidad de uv mide más o menos eso)
+    pub fn eval(&self, h: &Hit, footprint: f32) -> Vec3 { self.at(h.p, h.n, h.uv, footprint) }
+
+    // Normal de `h` con las alturas de la textura (su gris por `height`),
+    // derivadas a la escala del cono
+    pub fn bump(&self, h: &Hit, footprint: f32, height: f32) -> Vec3 {
+        let gray = |c: Vec3| (c.x + c.y + c.z) / 3.0;
+        let e = footprint.max(1e-3);
+        let (t, b) = tangents(h.n);
+        let [u, w] = h.uv;
+        let h0 = gray(self.at(h.p, h.n, h.uv, footprint));
+        let du = gray(self.at(h.p + t * e, h.n, [u + e, w], footprint)) - h0;
+        let dv = gray(self.at(h.p + b * e, h.n, [u, w + e], footprint)) - h0;
+        (h.n - (t * du + b * dv) * (height / e)).norm()
+    }
+
+    // Normal de `h` leída de un mapa en el espacio tangente (rgb = xyz en
+    // [0,1]); `strength` escala la inclinación
+    pub fn normal_map(&self, h: &Hit, footprint: f32, strength: f32) -> Vec3 {
+        let c = self.at(h.p, h.n, h.uv, footprint) * 2.0 - v(1.0);
+        let (t, b) = tangents(h.n);
+        (t * (c.x * strength) + b * (c.y * strength) + h.n * c.z.max(0.0)).norm()
+    }
+
+    fn at(&self, p: Vec3, n: Vec3, uv: [f32; 2], footprint: f32) -> Vec3 {
+        // un pelo hacia adentro, para que una cara justo sobre un entero no titile
+        let p = if self.uv { Vec3::new(uv[0], uv[1], 0.0) } else { p - n * 1e-4 };
+        let q = p * self.scale;
+        match &self.pattern {
+            Pattern::Checker => {
+                let k = q.x.floor() as i64 + q.y.floor() as i64 + q.z.floor() as i64;
+                if k.rem_euclid(2) == 0 { self.a } else { self.b }
+            }
+            &Pattern::Noise { octaves } => lerp(self.a, self.b, fbm3(q.x, q.y, q.z, self.seed, octaves)),
+            &Pattern::Wood { turbulence } => {
+                // el ruido estirado a lo largo de la veta tuerce los anillos
+                let warp = (fbm3(q.x, q.y * 0.2, q.z, self.seed, 3) - 0.5) * turbulence;
+                let r = (q.x * q.x + q.z * q.z).sqrt() + warp;
+                // claro al empezar el anillo y oscuro al cerrarlo
+                lerp(self.a, self.b, (r - r.floor()).powi(3))
+            }
+            &Pattern::Brick { size, gap, offset } => {
+                let (x, y) = if self.uv { (q.x, q.y) } else { planar(q, n) };
+                let row = (y / size[1]).floor();
+                let x = x / size[0] + offset * row;
+                let col = x.floor();
+                if (x - col) * size[0] < gap || (y / size[1] - row) * size[1] < gap { return self.b; }
+                // cada pieza con su tono
+                self.a * (0.85 + 0.3 * value2(col, row, self.seed))
+            }
+            &Pattern::Marble { turbulence } => {
+                let n = fbm3(q.x, q.y, q.z, self.seed, 5);
+                let s = ((q.x + q.y * 0.5 + turbulence * n) * PI).sin().abs();
+                // las vetas son los ceros del seno: finas y con el borde suave
+                lerp(self.a, self.b, (1.0 - s).powi(6))
+            }
+            Pattern::Image(m) => {
+                let (s, t, side) = if self.uv { (q.x, q.y, false) } else { face_st(q, n) };
+                let map = if side { m.side.as_ref().unwrap_or(&m.top) } else { &m.top };
+                let w = map.levels[0].0.max(map.levels[0].1) as f32;
+                map.sample(s, t, footprint * self.scale * w, m.wrap)
+            }
+        }
+    }
+}
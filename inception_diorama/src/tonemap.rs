@@ -0,0 +1,81 @@
+// src/tonemap.rs
+// Del búfer HDR lineal a píxeles de pantalla: exposición, curva de tonos
+// y la función de transferencia sRGB.
+use crate::math::Vec3;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ToneMap {
+    Clamp,    // recorta a [0,1], como antes
+    Reinhard, // x / (1 + x)
+    Aces,     // curva fílmica de Narkowicz (ajuste de ACES)
+}
+
+impl ToneMap {
+    pub const ALL: [ToneMap; 3] = [ToneMap::Clamp, ToneMap::Reinhard, ToneMap::Aces];
+
+    pub fn name(self) -> &'static str {
+        match self { ToneMap::Clamp => "clamp", ToneMap::Reinhard => "reinhard", ToneMap::Aces => "aces" }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == s)
+    }
+
+    // La siguiente de la lista (para la tecla del visor)
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&t| t == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    fn apply(self, x: f32) -> f32 {
+        let x = x.max(0.0);
+        match self {
+            ToneMap::Clamp => x.min(1.0),
+            ToneMap::Reinhard => x / (1.0 + x),
+            ToneMap::Aces => ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0),
+        }
+    }
+}
+
+// sRGB (un byte) a lineal, para colores que vienen de archivos de imagen o paletas
+pub fn srgb_decode(c: u8) -> f32 { srgb_to_linear(c as f32 / 255.0) }
+
+pub fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.040_45 { x / 12.92 } else { ((x + 0.055) / 1.055).powf(2.4) }
+}
+
+// Lineal [0,1] a sRGB
+fn srgb_encode(x: f32) -> f32 {
+    if x <= 0.003_130_8 { 12.92 * x } else { 1.055 * x.powf(1.0 / 2.4) - 0.055 }
+}
+
+#[derive(Copy, Clone)]
+pub struct Display {
+    pub tonemap: ToneMap,
+    pub exposure: f32, // en pasos (EV): cada +1 duplica el brillo
+}
+
+impl Default for Display {
+    fn default() -> Self { Self { tonemap: ToneMap::Aces, exposure: 0.0 } }
+}
+
+impl Display {
+    // Un color lineal a 0xAARRGGBB
+    pub fn encode(&self, c: Vec3) -> u32 {
+        let k = self.exposure.exp2();
+        let f = |x: f32| srgb_encode(self.tonemap.apply(x * k));
+        rgb_u32(f(c.x), f(c.y), f(c.z))
+    }
+
+    // `hdr` multiplicado por `scale` (1/muestras), a formato de ventana
+    pub fn resolve(&self, hdr: &[Vec3], scale: f32, fb: &mut [u32]) {
+        for (px, s) in fb.iter_mut().zip(hdr) {
+            *px = self.encode(*s * scale);
+        }
+    }
+}
+
+pub fn rgb_u32(r:f32,g:f32,b:f32)->u32{
+    let q = |x: f32| (x.clamp(0.0, 1.0)*255.0 + 0.5) as u32;
+    (255<<24) | (q(r)<<16) | (q(g)<<8) | q(b)
+}
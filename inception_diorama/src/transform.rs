@@ -0,0 +1,107 @@
+// src/transform.rs
+// Transformación afín: p' = m·p + t. Se arma como escala, después rotación
+// (ángulos de Euler en grados, aplicados en orden X, Y, Z) y al final traslación.
+use crate::math::Vec3;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Transform {
+    pub m: [[f32; 3]; 3], // filas
+    pub t: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self { m: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], t: Vec3::default() }
+    }
+}
+
+fn mul(a: &[[f32; 3]; 3], b: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut r = [[0.0; 3]; 3];
+    for (i, row) in r.iter_mut().enumerate() {
+        for (j, x) in row.iter_mut().enumerate() {
+            *x = a[i][0] * b[0][j] + a[i][1] * b[1][j] + a[i][2] * b[2][j];
+        }
+    }
+    r
+}
+
+// Rotación de Euler en grados: primero x, después y, después z
+pub fn euler(rot: Vec3) -> [[f32; 3]; 3] {
+    let (sx, cx) = rot.x.to_radians().sin_cos();
+    let (sy, cy) = rot.y.to_radians().sin_cos();
+    let (sz, cz) = rot.z.to_radians().sin_cos();
+    let rx = [[1.0, 0.0, 0.0], [0.0, cx, -sx], [0.0, sx, cx]];
+    let ry = [[cy, 0.0, sy], [0.0, 1.0, 0.0], [-sy, 0.0, cy]];
+    let rz = [[cz, -sz, 0.0], [sz, cz, 0.0], [0.0, 0.0, 1.0]];
+    mul(&rz, &mul(&ry, &rx))
+}
+
+// Cuaternión (w, x, y, z) a matriz de rotación; se normaliza antes
+pub fn quat(q: [f32; 4]) -> [[f32; 3]; 3] {
+    let l = q.iter().map(|c| c * c).sum::<f32>().sqrt().max(1e-12);
+    let [w, x, y, z] = q.map(|c| c / l);
+    [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+        [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+        [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+    ]
+}
+
+// La misma rotación que `euler`, como cuaternión: qz·qy·qx
+pub fn euler_quat(rot: Vec3) -> [f32; 4] {
+    let half = |a: f32| (a.to_radians() * 0.5).sin_cos();
+    let ((sx, cx), (sy, cy), (sz, cz)) = (half(rot.x), half(rot.y), half(rot.z));
+    [
+        cz * cy * cx + sz * sy * sx,
+        cz * cy * sx - sz * sy * cx,
+        cz * sy * cx + sz * cy * sx,
+        sz * cy * cx - cz * sy * sx,
+    ]
+}
+
+impl Transform {
+    pub fn new(pos: Vec3, rot: Vec3, scale: Vec3) -> Self {
+        let s = [[scale.x, 0.0, 0.0], [0.0, scale.y, 0.0], [0.0, 0.0, scale.z]];
+        Self { m: mul(&euler(rot), &s), t: pos }
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    pub fn point(&self, p: Vec3) -> Vec3 { self.vector(p) + self.t }
+
+    // Inversa por cofactores: m⁻¹ = adj(m) / det, t⁻¹ = -m⁻¹·t
+    pub fn inverse(&self) -> Self {
+        let m = &self.m;
+        let c = |i: usize, j: usize| {
+            let (i0, i1, j0, j1) = ((i + 1) % 3, (i + 2) % 3, (j + 1) % 3, (j + 2) % 3);
+            m[i0][j0] * m[i1][j1] - m[i0][j1] * m[i1][j0]
+        };
+        let det = m[0][0] * c(0, 0) + m[0][1] * c(0, 1) + m[0][2] * c(0, 2);
+        let inv_det = if det.abs() > 1e-12 { 1.0 / det } else { 0.0 };
+        let mut r = [[0.0; 3]; 3];
+        for (i, row) in r.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() { *x = c(j, i) * inv_det; }
+        }
+        let inv = Self { m: r, t: Vec3::default() };
+        Self { t: -inv.vector(self.t), ..inv }
+    }
+
+    // Normales: con la inversa transpuesta (la matriz de cofactores, que
+    // difiere solo en la escala), así una escala no uniforme no las tuerce
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        let c0 = Vec3::new(self.m[0][0], self.m[1][0], self.m[2][0]);
+        let c1 = Vec3::new(self.m[0][1], self.m[1][1], self.m[2][1]);
+        let c2 = Vec3::new(self.m[0][2], self.m[1][2], self.m[2][2]);
+        let (r0, r1, r2) = (c1.cross(c2), c2.cross(c0), c0.cross(c1));
+        let det = c0.dot(r0);
+        let out = r0 * n.x + r1 * n.y + r2 * n.z;
+        (if det < 0.0 { -out } else { out }).norm()
+    }
+}
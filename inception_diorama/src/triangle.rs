@@ -0,0 +1,50 @@
+// src/triangle.rs
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::shape::Shape;
+
+// Normal según el orden a→b→c (antihorario visto de frente)
+#[derive(Copy, Clone)]
+pub struct Triangle { pub a: Vec3, pub b: Vec3, pub c: Vec3, pub mat_id: usize }
+
+impl Shape for Triangle {
+    // Möller-Trumbore; se ve de los dos lados. uv son las baricéntricas de b y c.
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        let e1 = self.b - self.a;
+        let e2 = self.c - self.a;
+        let pv = ray.d.cross(e2);
+        let det = e1.dot(pv);
+        if det.abs() < 1e-10 { return None; }
+        let inv = 1.0 / det;
+        let tv = ray.o - self.a;
+        let u = tv.dot(pv) * inv;
+        if !(0.0..=1.0).contains(&u) { return None; }
+        let qv = tv.cross(e1);
+        let w = ray.d.dot(qv) * inv;
+        if w < 0.0 || u + w > 1.0 { return None; }
+        let t = e2.dot(qv) * inv;
+        if t <= tmin || t >= tmax { return None; }
+        Some(Hit { t, p: ray.at(t), n: e1.cross(e2).norm(), mat_id: self.mat_id, uv: [u, w] })
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let (a, b, c) = (self.a, self.b, self.c);
+        // un poco de grosor para los triángulos alineados a un eje
+        let pad = Vec3::new(1e-4, 1e-4, 1e-4);
+        let min = Vec3::new(a.x.min(b.x).min(c.x), a.y.min(b.y).min(c.y), a.z.min(b.z).min(c.z)) - pad;
+        let max = Vec3::new(a.x.max(b.x).max(c.x), a.y.max(b.y).max(c.y), a.z.max(b.z).max(c.z)) + pad;
+        Some(Aabb { min, max, mat_id: self.mat_id })
+    }
+
+    fn mat_id(&self) -> usize { self.mat_id }
+
+    fn area(&self) -> f32 { (self.b - self.a).cross(self.c - self.a).len() * 0.5 }
+
+    fn sample_surface(&self, u: Vec3) -> (Vec3, Vec3) {
+        let s = u.x.sqrt();
+        let (wb, wc) = (s * (1.0 - u.y), s * u.y);
+        let p = self.a + (self.b - self.a) * wb + (self.c - self.a) * wc;
+        (p, (self.b - self.a).cross(self.c - self.a).norm())
+    }
+}
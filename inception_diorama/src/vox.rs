@@ -0,0 +1,357 @@
+// src/vox.rs
+// Lector de archivos .vox de MagicaVoxel: modelos (SIZE + XYZI), paleta
+// (RGBA, o la de fábrica si falta), materiales (MATL) y el grafo de escena
+// (nTRN / nGRP / nSHP) que dice dónde va cada modelo. Todo se devuelve ya
+// en bloques con y hacia arriba: MagicaVoxel usa z arriba, (x, y, z) → (x, z, -y).
+use std::collections::HashMap;
+use std::path::Path;
+use crate::math::Vec3;
+use crate::material::{Material, Kind};
+use crate::tonemap::srgb_decode;
+
+pub struct Vox {
+    pub voxels: Vec<([i32; 3], u8)>, // posición y color (1-255)
+    pub palette: [[u8; 4]; 256],     // RGBA; el color i es palette[i]
+    matl: HashMap<u8, HashMap<String, String>>,
+}
+
+// Cursor sobre los bytes del archivo (todo little-endian)
+struct Reader<'a> { data: &'a [u8], pos: usize }
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(n).filter(|&e| e <= self.data.len()).ok_or("archivo cortado")?;
+        let b = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(b)
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn count(&mut self) -> Result<usize, String> {
+        usize::try_from(self.i32()?).map_err(|_| "cantidad negativa".to_string())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let n = self.count()?;
+        Ok(String::from_utf8_lossy(self.bytes(n)?).into_owned())
+    }
+
+    fn dict(&mut self) -> Result<HashMap<String, String>, String> {
+        let n = self.count()?;
+        (0..n).map(|_| Ok((self.string()?, self.string()?))).collect()
+    }
+}
+
+// Rotación entera (filas) y traslación en bloques
+type Xf = ([[i32; 3]; 3], [i32; 3]);
+
+const IDENTITY: Xf = ([[1, 0, 0], [0, 1, 0], [0, 0, 1]], [0, 0, 0]);
+
+// None si se sale de i32 (las traslaciones del archivo pueden ser cualquier cosa)
+fn apply(&(m, t): &Xf, v: [i32; 3]) -> Option<[i32; 3]> {
+    let row = |i: usize| (0..3).try_fold(t[i], |acc, k| acc.checked_add(m[i][k].checked_mul(v[k])?));
+    Some([row(0)?, row(1)?, row(2)?])
+}
+
+// padre ∘ hijo; las rotaciones tienen un solo ±1 por fila, así que solo
+// la traslación puede desbordar
+fn compose(parent: &Xf, child: &Xf) -> Option<Xf> {
+    let (pm, _) = parent;
+    let m = [0, 1, 2].map(|i| [0, 1, 2].map(|j| (0..3).map(|k| pm[i][k] * child.0[k][j]).sum()));
+    Some((m, apply(parent, child.1)?))
+}
+
+// `_r`: en los bits 0-1 y 2-3, la columna no nula de las filas 0 y 1; los bits 4-6, los signos
+fn rotation(r: u8) -> [[i32; 3]; 3] {
+    let (i0, i1) = ((r & 3) as usize, ((r >> 2) & 3) as usize);
+    let i2 = 3usize.saturating_sub(i0 + i1).min(2);
+    let mut m = [[0; 3]; 3];
+    for (row, (col, bit)) in [(i0, 4), (i1, 5), (i2, 6)].into_iter().enumerate() {
+        m[row][col] = if r >> bit & 1 == 1 { -1 } else { 1 };
+    }
+    m
+}
+
+enum Node {
+    Transform { xf: Xf, child: i32 },
+    Group(Vec<i32>),
+    Shape(Vec<usize>),
+}
+
+pub fn load(path: &Path) -> Result<Vox, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    parse(&data).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn parse(data: &[u8]) -> Result<Vox, String> {
+    let mut r = Reader { data, pos: 0 };
+    if r.bytes(4)? != b"VOX " { return Err("no es un archivo .vox".into()); }
+    r.i32()?; // versión
+    if r.bytes(4)? != b"MAIN" { return Err("falta el chunk MAIN".into()); }
+    r.bytes(8)?;
+
+    let mut sizes: Vec<[i32; 3]> = Vec::new();
+    let mut models: Vec<Vec<([i32; 3], u8)>> = Vec::new();
+    let mut palette = default_palette();
+    let mut matl = HashMap::new();
+    let mut nodes: HashMap<i32, Node> = HashMap::new();
+
+    while r.pos < data.len() {
+        let id: [u8; 4] = r.bytes(4)?.try_into().unwrap();
+        let (content, children) = (r.count()?, r.count()?);
+        let mut c = Reader { data: r.bytes(content)?, pos: 0 };
+        r.bytes(children)?; // los hijos de MAIN vienen sueltos, a continuación
+        match &id {
+            b"SIZE" => sizes.push([c.i32()?, c.i32()?, c.i32()?]),
+            b"XYZI" => {
+                let n = c.count()?;
+                let raw = c.bytes(n * 4)?;
+                models.push(raw.chunks_exact(4).map(|v| ([v[0] as i32, v[1] as i32, v[2] as i32], v[3])).collect());
+            }
+            b"RGBA" => {
+                let raw = c.bytes(256 * 4)?;
+                // el color i (1-255) es la entrada i-1 del chunk
+                for i in 0..255 { palette[i + 1].copy_from_slice(&raw[i * 4..i * 4 + 4]); }
+            }
+            b"MATL" => {
+                let id = c.i32()?;
+                let d = c.dict()?;
+                if (1..=255).contains(&id) { matl.insert(id as u8, d); }
+            }
+            b"nTRN" => {
+                let node = c.i32()?;
+                c.dict()?;
+                let child = c.i32()?;
+                c.i32()?; // reservado
+                c.i32()?; // capa
+                let frames = c.count()?;
+                let mut xf = IDENTITY;
+                if frames > 0 {
+                    let f = c.dict()?;
+                    if let Some(rot) = f.get("_r") { xf.0 = rotation(rot.parse().map_err(|_| "_r inválido")?); }
+                    if let Some(t) = f.get("_t") {
+                        let v: Vec<i32> = t.split_whitespace().filter_map(|w| w.parse().ok()).collect();
+                        if let [x, y, z] = v[..] { xf.1 = [x, y, z]; }
+                    }
+                }
+                nodes.insert(node, Node::Transform { xf, child });
+            }
+            b"nGRP" => {
+                let node = c.i32()?;
+                c.dict()?;
+                let n = c.count()?;
+                let kids = (0..n).map(|_| c.i32()).collect::<Result<_, _>>()?;
+                nodes.insert(node, Node::Group(kids));
+            }
+            b"nSHP" => {
+                let node = c.i32()?;
+                c.dict()?;
+                let n = c.count()?;
+                let mut ids = Vec::with_capacity(n);
+                for _ in 0..n {
+                    ids.push(c.count()?);
+                    c.dict()?;
+                }
+                nodes.insert(node, Node::Shape(ids));
+            }
+            // capas, cámaras, render y demás no cambian la geometría
+            _ => {}
+        }
+    }
+    if models.len() != sizes.len() { return Err("SIZE y XYZI no se corresponden".into()); }
+
+    // Dónde va cada modelo. Sin grafo (archivos viejos), todos en el origen y sin centrar.
+    let mut placed: Vec<(usize, Xf)> = Vec::new();
+    if nodes.is_empty() {
+        placed.extend((0..models.len()).map(|i| (i, IDENTITY)));
+    } else {
+        let (mut stack, mut visits) = (vec![(0, IDENTITY)], 0);
+        while let Some((id, xf)) = stack.pop() {
+            // un grafo mal armado no puede hacer dar vueltas para siempre
+            visits += 1;
+            if visits > 1 << 20 { return Err("grafo de escena con ciclos".into()); }
+            match nodes.get(&id) {
+                Some(Node::Transform { xf: local, child }) => {
+                    stack.push((*child, compose(&xf, local).ok_or("traslación fuera de rango")?));
+                }
+                Some(Node::Group(kids)) => stack.extend(kids.iter().map(|&k| (k, xf))),
+                Some(Node::Shape(ids)) => placed.extend(ids.iter().map(|&m| (m, xf))),
+                None => return Err(format!("nodo {id} inexistente")),
+            }
+        }
+    }
+
+    let mut voxels = Vec::new();
+    for (m, xf) in placed {
+        let (Some(model), Some(size)) = (models.get(m), sizes.get(m)) else { return Err(format!("modelo {m} inexistente")) };
+        // con grafo, el modelo va centrado en su traslación
+        let half = if nodes.is_empty() { [0; 3] } else { size.map(|s| s / 2) };
+        for &(v, color) in model {
+            let p = apply(&xf, [v[0] - half[0], v[1] - half[1], v[2] - half[2]]);
+            let Some([x, y, z]) = p.filter(|p| p[1] != i32::MIN) else { return Err("traslación fuera de rango".into()) };
+            voxels.push(([x, z, -y], color));
+        }
+    }
+    Ok(Vox { voxels, palette, matl })
+}
+
+impl Vox {
+    // El color `i` de la paleta como material, con lo que diga su MATL
+    pub fn material(&self, i: u8) -> Material {
+        let [r, g, b, _] = self.palette[i as usize];
+        let albedo = Vec3::new(srgb_decode(r), srgb_decode(g), srgb_decode(b));
+        let mut m = Material { kind: Kind::Diffuse, albedo, specular: 0.05, transparency: 0.0, reflectivity: 0.02, texture: None, relief: None };
+        let Some(d) = self.matl.get(&i) else { return m };
+        let num = |k: &str, def: f32| d.get(k).and_then(|v| v.parse::<f32>().ok()).unwrap_or(def);
+        let rough = num("_rough", 0.1).clamp(0.02, 1.0);
+        match d.get("_type").map(String::as_str) {
+            Some("_metal") if num("_plastic", 0.0) > 0.0 => {
+                m.kind = Kind::Plastic { rough };
+                m.reflectivity = num("_sp", 0.04).min(1.0);
+            }
+            Some("_metal") => {
+                m.kind = Kind::Metal { rough };
+                m.specular = 1.0;
+                m.reflectivity = num("_metal", 1.0).clamp(0.0, 1.0);
+            }
+            Some("_glass") => {
+                // MagicaVoxel guarda el índice de refracción menos uno
+                let ior = num("_ior", 0.5);
+                m.kind = Kind::Dielectric { ior: if ior < 1.0 { ior + 1.0 } else { ior }, absorption: Vec3::default() };
+                m.transparency = num("_trans", num("_alpha", 1.0)).clamp(0.0, 1.0);
+                m.specular = 0.04;
+                m.reflectivity = 0.04;
+            }
+            Some("_emit") => {
+                m.kind = Kind::Emissive { intensity: num("_emit", 1.0) * (1.0 + num("_flux", 0.0)) * 4.0 };
+                m.specular = 0.0;
+                m.reflectivity = 0.0;
+            }
+            _ => {}
+        }
+        m
+    }
+}
+
+// La paleta de fábrica: el cubo de 6 niveles por canal (sin el negro),
+// de blanco hacia abajo, y después rampas de rojo, verde, azul y gris
+fn default_palette() -> [[u8; 4]; 256] {
+    let mut p = [[0u8; 4]; 256];
+    let mut k = 1;
+    let steps = [255u8, 204, 153, 102, 51, 0];
+    for r in steps {
+        for g in steps {
+            for b in steps {
+                if r == 0 && g == 0 && b == 0 { continue; }
+                p[k] = [r, g, b, 255];
+                k += 1;
+            }
+        }
+    }
+    let ramp = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+    for channel in [[1, 0, 0], [0, 1, 0], [0, 0, 1], [1, 1, 1]] {
+        for v in ramp {
+            p[k] = [v * channel[0], v * channel[1], v * channel[2], 255];
+            k += 1;
+        }
+    }
+    p
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut c = id.to_vec();
+        c.extend((content.len() as i32).to_le_bytes());
+        c.extend(0i32.to_le_bytes());
+        c.extend(content);
+        c
+    }
+
+    fn ints(v: &[i32]) -> Vec<u8> { v.iter().flat_map(|i| i.to_le_bytes()).collect() }
+
+    fn file(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut f = b"VOX ".to_vec();
+        f.extend(150i32.to_le_bytes());
+        f.extend(b"MAIN");
+        f.extend(0i32.to_le_bytes());
+        f.extend((body.len() as i32).to_le_bytes());
+        f.extend(body);
+        f
+    }
+
+    // Un modelo de 4³ con dos bloques, sin paleta ni grafo
+    fn two_blocks() -> Vec<u8> {
+        file(&[chunk(b"SIZE", &ints(&[4, 4, 4])), chunk(b"XYZI", &[ints(&[2]), vec![1, 2, 3, 1, 0, 0, 0, 6]].concat())])
+    }
+
+    #[test]
+    fn model_without_palette() {
+        let vox = parse(&two_blocks()).unwrap();
+        // z arriba pasa a y arriba
+        assert_eq!(vox.voxels, vec![([1, 3, -2], 1), ([0, 0, 0], 6)]);
+        // la paleta de siempre: blanco y después amarillo
+        let white = vox.material(1).albedo;
+        assert!(white.x == 1.0 && white.y == 1.0 && white.z == 1.0);
+        let yellow = vox.material(6).albedo;
+        assert!(yellow.x == 1.0 && yellow.y == 1.0 && yellow.z == 0.0);
+    }
+
+    #[test]
+    fn glass_material() {
+        let mut matl = ints(&[5, 2]);
+        for s in ["_type", "_glass", "_ior", "0.3"] { matl.extend(ints(&[s.len() as i32])); matl.extend(s.as_bytes()); }
+        let mut data = two_blocks();
+        data.extend(chunk(b"MATL", &matl));
+        let n = (data.len() - 20) as i32;
+        data[16..20].copy_from_slice(&n.to_le_bytes());
+        let vox = parse(&data).unwrap();
+        match vox.material(5).kind {
+            Kind::Dielectric { ior, .. } => assert!((ior - 1.3).abs() < 1e-6),
+            _ => panic!("no es vidrio"),
+        }
+    }
+
+    #[test]
+    fn malformed_files() {
+        assert!(parse(b"VOY ").is_err());
+        let data = two_blocks();
+        for cut in [3, 10, 30, data.len() - 1] {
+            assert!(parse(&data[..cut]).is_err(), "cortado en {cut}");
+        }
+        // XYZI sin su SIZE
+        assert!(parse(&file(&[chunk(b"XYZI", &ints(&[0]))])).is_err());
+        // cantidad negativa
+        assert!(parse(&file(&[chunk(b"SIZE", &ints(&[1, 1, 1])), chunk(b"XYZI", &ints(&[-1]))])).is_err());
+        // un grafo que apunta a un nodo que no está
+        assert!(parse(&file(&[chunk(b"nGRP", &ints(&[0, 0, 1, 9]))])).is_err());
+        // dos traslaciones que juntas se salen de i32
+        let text = |s: &str| [ints(&[s.len() as i32]), s.as_bytes().to_vec()].concat();
+        let trn = |node: i32, child: i32| chunk(b"nTRN", &[ints(&[node, 0, child, -1, 0, 1, 1]), text("_t"), text("2000000000 0 0")].concat());
+        let size = chunk(b"SIZE", &ints(&[1, 1, 1]));
+        let xyzi = chunk(b"XYZI", &[ints(&[1]), vec![0, 0, 0, 1]].concat());
+        let far = file(&[size, xyzi, trn(0, 1), trn(1, 2), chunk(b"nSHP", &ints(&[2, 0, 1, 0, 0]))]);
+        assert!(parse(&far).is_err_and(|e| e.contains("fuera de rango")));
+    }
+
+    // Como la de MagicaVoxel (0xAABBGGRR): el cubo de blanco hacia abajo,
+    // después las rampas de rojo, verde, azul y gris
+    #[test]
+    fn default_palette_order() {
+        let p = default_palette();
+        assert_eq!(p[1], [255, 255, 255, 255]);
+        assert_eq!(p[2], [255, 255, 204, 255]);
+        assert_eq!(p[215], [0, 0, 51, 255]);
+        assert_eq!(p[216], [0xee, 0, 0, 255]);
+        assert_eq!(p[226], [0, 0xee, 0, 255]);
+        assert_eq!(p[236], [0, 0, 0xee, 255]);
+        assert_eq!(p[246], [0xee, 0xee, 0xee, 255]);
+        assert_eq!(p[255], [0x11, 0x11, 0x11, 255]);
+    }
+}
@@ -0,0 +1,364 @@
+// src/voxel.rs
+// Mundo de bloques: una grilla de ids (u8, 0 = aire) partida en chunks de
+// 16³ que solo existen si tienen algo. Cada id se mapea a un material. Se
+// recorre con el DDA de Amanatides-Woo en tres niveles: chunk por chunk;
+// en los que no están vacíos, de a ladrillos de 4³ (un bit por ladrillo
+// dice si tiene algo); y en los ladrillos ocupados, bloque por bloque.
+use crate::math::Vec3;
+use crate::ray::Ray;
+use crate::aabb::{Aabb, Hit};
+use crate::shape::Shape;
+use crate::noise::fbm2;
+
+pub const CHUNK: usize = 16;
+// Lo más largo que se acepta por eje: los chunks se crean a medida, pero
+// la tabla de chunks va entera (hasta 64³ = 2^18 lugares)
+pub const MAX_DIM: usize = 1024;
+const BRICK: usize = 4;
+
+struct Chunk {
+    blocks: [u8; CHUNK * CHUNK * CHUNK],
+    bricks: u64, // bit (z·4 + y)·4 + x: el ladrillo de 4³ tiene algún bloque
+}
+
+// Lo que se le hizo a la grilla, en orden, para poder volver a escribirlo
+#[derive(Copy, Clone)]
+pub enum VoxelEdit {
+    // bloques de min a max, inclusive; id 0 vacía
+    Fill { id: u8, min: [i32; 3], max: [i32; 3] },
+    // columnas de altura base + height·fbm: `top` arriba, `under` 3 más, `deep` el resto
+    Terrain { top: u8, under: u8, deep: u8, seed: u32, base: f32, height: f32, freq: f32 },
+}
+
+pub struct VoxelGrid {
+    pub origin: Vec3,
+    pub scale: f32, // lado de un bloque en el mundo
+    pub dims: [usize; 3],
+    pub palette: Vec<Option<usize>>, // id → material
+    pub edits: Vec<VoxelEdit>,
+    pub source: Option<(String, Vec3)>, // archivo y posición, si salió de una línea `vox`
+    nch: [usize; 3],
+    chunks: Vec<Option<Box<Chunk>>>,
+}
+
+fn comp(v: Vec3, i: usize) -> f32 { match i { 0 => v.x, 1 => v.y, _ => v.z } }
+
+impl VoxelGrid {
+    pub fn new(origin: Vec3, dims: [usize; 3], scale: f32) -> Self {
+        let nch = dims.map(|d| d.div_ceil(CHUNK));
+        let chunks = (0..nch[0] * nch[1] * nch[2]).map(|_| None).collect();
+        Self { origin, scale, dims, palette: vec![None; 256], edits: Vec::new(), source: None, nch, chunks }
+    }
+
+    fn chunk_index(&self, c: [usize; 3]) -> usize { (c[2] * self.nch[1] + c[1]) * self.nch[0] + c[0] }
+
+    fn local_index(x: usize, y: usize, z: usize) -> usize { ((z % CHUNK) * CHUNK + y % CHUNK) * CHUNK + x % CHUNK }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, id: u8) {
+        if x >= self.dims[0] || y >= self.dims[1] || z >= self.dims[2] { return; }
+        let ci = self.chunk_index([x / CHUNK, y / CHUNK, z / CHUNK]);
+        match &mut self.chunks[ci] {
+            Some(c) => c.blocks[Self::local_index(x, y, z)] = id,
+            None if id == 0 => {}
+            slot => slot.insert(Box::new(Chunk { blocks: [0; CHUNK * CHUNK * CHUNK], bricks: 0 })).blocks[Self::local_index(x, y, z)] = id,
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        if x >= self.dims[0] || y >= self.dims[1] || z >= self.dims[2] { return 0; }
+        match &self.chunks[self.chunk_index([x / CHUNK, y / CHUNK, z / CHUNK])] {
+            Some(c) => c.blocks[Self::local_index(x, y, z)],
+            None => 0,
+        }
+    }
+
+    // Bloques no vacíos
+    pub fn count(&self) -> usize {
+        self.chunks.iter().flatten().map(|c| c.blocks.iter().filter(|&&id| id != 0).count()).sum()
+    }
+
+    pub fn apply(&mut self, edit: VoxelEdit) {
+        let [nx, ny, nz] = self.dims;
+        match edit {
+            VoxelEdit::Fill { id, min, max } => {
+                let lo = |i: usize| min[i].max(0) as usize;
+                let hi = |i: usize| max[i].saturating_add(1).clamp(0, self.dims[i] as i32) as usize;
+                let (lo, hi) = ([lo(0), lo(1), lo(2)], [hi(0), hi(1), hi(2)]);
+                for z in lo[2]..hi[2] { for y in lo[1]..hi[1] { for x in lo[0]..hi[0] { self.set(x, y, z, id); } } }
+            }
+            VoxelEdit::Terrain { top, under, deep, seed, base, height, freq } => {
+                for z in 0..nz {
+                    for x in 0..nx {
+                        let h = base + height * fbm2(x as f32 * freq, z as f32 * freq, seed, 4);
+                        let h = (h.round().max(0.0) as usize).min(ny);
+                        for y in 0..h {
+                            let id = if y + 1 == h { top } else if y + 4 >= h { under } else { deep };
+                            self.set(x, y, z, id);
+                        }
+                    }
+                }
+            }
+        }
+        self.edits.push(edit);
+    }
+
+    // Después de editar: suelta los chunks que quedaron vacíos (por ejemplo
+    // tras un `fill 0`) y arma la máscara de ladrillos de los demás
+    pub fn finish(&mut self) {
+        for slot in &mut self.chunks {
+            let Some(c) = slot else { continue };
+            c.bricks = 0;
+            for (i, &id) in c.blocks.iter().enumerate() {
+                if id == 0 { continue; }
+                let (x, y, z) = (i % CHUNK / BRICK, i / CHUNK % CHUNK / BRICK, i / (CHUNK * CHUNK) / BRICK);
+                c.bricks |= 1 << ((z * 4 + y) * 4 + x);
+            }
+            if c.bricks == 0 { *slot = None; }
+        }
+    }
+
+    // Mallado goloso: junta bloques vecinos del mismo material en la menor
+    // cantidad de cajas que se pueda encontrar sin buscar de más. Desde cada
+    // bloque libre se estira en x, después la fila entera en y y después el
+    // rectángulo entero en z, mientras lo que se agrega siga libre e igual.
+    pub fn greedy(&self) -> Vec<Aabb> {
+        let [nx, ny, nz] = self.dims;
+        let mat = |x: usize, y: usize, z: usize| match self.get(x, y, z) {
+            0 => None,
+            id => Some(self.palette[id as usize].unwrap_or(0)),
+        };
+        // lo ya usado, solo en los chunks que existen: los bloques vacíos no se marcan
+        let mut taken: Vec<Option<Box<[bool]>>> = self.chunks.iter()
+            .map(|c| c.as_ref().map(|_| vec![false; CHUNK * CHUNK * CHUNK].into_boxed_slice()))
+            .collect();
+        let slot = |x: usize, y: usize, z: usize| (self.chunk_index([x / CHUNK, y / CHUNK, z / CHUNK]), Self::local_index(x, y, z));
+        let is_taken = |taken: &[Option<Box<[bool]>>], x: usize, y: usize, z: usize| {
+            let (c, i) = slot(x, y, z);
+            taken[c].as_ref().is_some_and(|t| t[i])
+        };
+        let mut boxes = Vec::new();
+        for z in 0..nz {
+            for y in 0..ny {
+                let mut x = 0;
+                while x < nx {
+                    // los chunks que no existen se saltan enteros
+                    if self.chunks[self.chunk_index([x / CHUNK, y / CHUNK, z / CHUNK])].is_none() {
+                        x = (x / CHUNK + 1) * CHUNK;
+                        continue;
+                    }
+                    let (x0, m) = (x, mat(x, y, z));
+                    x += 1;
+                    let Some(m) = m else { continue };
+                    if is_taken(&taken, x0, y, z) { continue; }
+                    let free = |taken: &[Option<Box<[bool]>>], x: usize, y: usize, z: usize| mat(x, y, z) == Some(m) && !is_taken(taken, x, y, z);
+                    let mut x1 = x0 + 1;
+                    while x1 < nx && free(&taken, x1, y, z) { x1 += 1; }
+                    let mut y1 = y + 1;
+                    while y1 < ny && (x0..x1).all(|i| free(&taken, i, y1, z)) { y1 += 1; }
+                    let mut z1 = z + 1;
+                    while z1 < nz && (y..y1).all(|j| (x0..x1).all(|i| free(&taken, i, j, z1))) { z1 += 1; }
+                    for k in z..z1 {
+                        for j in y..y1 {
+                            for i in x0..x1 {
+                                let (c, l) = slot(i, j, k);
+                                if let Some(t) = &mut taken[c] { t[l] = true; }
+                            }
+                        }
+                    }
+                    let corner = |x: usize, y: usize, z: usize| self.origin + Vec3::new(x as f32, y as f32, z as f32) * self.scale;
+                    boxes.push(Aabb { min: corner(x0, y, z), max: corner(x1, y1, z1), mat_id: m });
+                }
+            }
+        }
+        boxes
+    }
+}
+
+// Un paso del DDA en una grilla de celdas de lado `size`, acotada a [lo, hi]
+struct Dda { cell: [i32; 3], step: [i32; 3], next: [f32; 3], delta: [f32; 3], axis: usize }
+
+impl Dda {
+    fn new(o: Vec3, d: Vec3, t: f32, size: f32, lo: [i32; 3], hi: [i32; 3], axis: usize) -> Self {
+        let mut s = Dda { cell: [0; 3], step: [0; 3], next: [f32::INFINITY; 3], delta: [f32::INFINITY; 3], axis };
+        for i in 0..3 {
+            let (oi, di) = (comp(o, i), comp(d, i));
+            // la celda del punto de partida; el clamp absorbe el error de redondeo en los bordes
+            s.cell[i] = (((oi + di * t) / size).floor() as i32).clamp(lo[i], hi[i]);
+            if di != 0.0 {
+                s.step[i] = if di > 0.0 { 1 } else { -1 };
+                let edge = (s.cell[i] + (di > 0.0) as i32) as f32 * size;
+                s.next[i] = (edge - oi) / di;
+                s.delta[i] = size / di.abs();
+            }
+        }
+        s
+    }
+
+    // Cruza a la celda vecina por el borde más cercano; devuelve la t del cruce
+    fn advance(&mut self) -> f32 {
+        let a = if self.next[0] < self.next[1] { if self.next[0] < self.next[2] { 0 } else { 2 } }
+                else if self.next[1] < self.next[2] { 1 } else { 2 };
+        let t = self.next[a];
+        self.cell[a] += self.step[a];
+        self.next[a] += self.delta[a];
+        self.axis = a;
+        t
+    }
+
+    fn exit(&self) -> f32 { self.next[0].min(self.next[1]).min(self.next[2]) }
+}
+
+impl Shape for VoxelGrid {
+    fn hit(&self, ray: &Ray, tmin: f32, tmax: f32) -> Option<Hit> {
+        // en coordenadas de grilla (un bloque = 1); sin normalizar d, la t es la misma
+        let o = (ray.o - self.origin) / self.scale;
+        let d = ray.d / self.scale;
+
+        // recorte contra la caja de la grilla, recordando por qué cara se entra
+        let (mut t0, mut t1, mut axis) = (tmin, tmax, usize::MAX);
+        for i in 0..3 {
+            let (oi, di, n) = (comp(o, i), comp(d, i), self.dims[i] as f32);
+            if di == 0.0 {
+                if oi < 0.0 || oi > n { return None; }
+                continue;
+            }
+            let (mut a, mut b) = ((0.0 - oi) / di, (n - oi) / di);
+            if a > b { std::mem::swap(&mut a, &mut b); }
+            if a > t0 { t0 = a; axis = i; }
+            t1 = t1.min(b);
+        }
+        if t0 >= t1 { return None; }
+        // si el rayo sale de adentro de un bloque, ese bloque no cuenta
+        let inside = axis == usize::MAX;
+
+        // el bloque impactado: normal por el eje que se cruzó al entrar
+        let hit_block = |id: u8, t: f32, axis: usize, step: [i32; 3]| {
+            let mut n = Vec3::default();
+            let s = -step[axis] as f32;
+            match axis { 0 => n.x = s, 1 => n.y = s, _ => n.z = s }
+            let g = o + d * t;
+            let f = |k: usize| comp(g, k) - comp(g, k).floor();
+            let uv = match axis { 0 => [f(2), f(1)], 1 => [f(0), f(2)], _ => [f(0), f(1)] };
+            Hit { t, p: ray.at(t), n, mat_id: self.palette[id as usize].unwrap_or(0), uv }
+        };
+        let outside = |c: [i32; 3], lo: [i32; 3], hi: [i32; 3]| (0..3).any(|i| c[i] < lo[i] || c[i] > hi[i]);
+
+        let hi_ch = self.nch.map(|n| n as i32 - 1);
+        let mut chunks = Dda::new(o, d, t0, CHUNK as f32, [0; 3], hi_ch, axis);
+        let (mut tc, mut first) = (t0, true);
+        while tc < t1 && !outside(chunks.cell, [0; 3], hi_ch) {
+            if let Some(chunk) = &self.chunks[self.chunk_index(chunks.cell.map(|x| x as usize))] {
+                let t_chunk = chunks.exit().min(t1);
+                let (blo, n) = (chunks.cell.map(|x| x * 4), CHUNK as i32 / BRICK as i32);
+                let bhi = blo.map(|x| x + n - 1);
+                let mut bricks = Dda::new(o, d, tc, BRICK as f32, blo, bhi, chunks.axis);
+                let mut tb = tc;
+                while tb < t_chunk && !outside(bricks.cell, blo, bhi) {
+                    let [bx, by, bz] = [0, 1, 2].map(|i| (bricks.cell[i] - blo[i]) as u32);
+                    if chunk.bricks >> ((bz * 4 + by) * 4 + bx) & 1 != 0 {
+                        let t_brick = bricks.exit().min(t_chunk);
+                        let lo = bricks.cell.map(|x| x * BRICK as i32);
+                        let hi = lo.map(|x| x + BRICK as i32 - 1);
+                        let mut cells = Dda::new(o, d, tb, 1.0, lo, hi, bricks.axis);
+                        let mut tv = tb;
+                        while tv < t_brick && !outside(cells.cell, lo, hi) {
+                            let [x, y, z] = cells.cell.map(|x| x as usize);
+                            let id = chunk.blocks[Self::local_index(x, y, z)];
+                            if id != 0 && !(inside && first) && cells.axis < 3 {
+                                return Some(hit_block(id, tv, cells.axis, cells.step));
+                            }
+                            first = false;
+                            tv = cells.advance();
+                        }
+                    }
+                    first = false;
+                    tb = bricks.advance();
+                }
+            }
+            first = false;
+            tc = chunks.advance();
+        }
+        None
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let size = Vec3::new(self.dims[0] as f32, self.dims[1] as f32, self.dims[2] as f32) * self.scale;
+        Some(Aabb { min: self.origin, max: self.origin + size, mat_id: self.mat_id() })
+    }
+
+    fn mat_id(&self) -> usize { self.palette.iter().flatten().next().copied().unwrap_or(0) }
+
+    // Como las instancias, no se muestrea como luz: los bloques emisivos
+    // iluminan solo cuando un camino los encuentra
+    fn area(&self) -> f32 { 0.0 }
+
+    fn sample_surface(&self, _u: Vec3) -> (Vec3, Vec3) { (self.origin, Vec3::new(0.0, 1.0, 0.0)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(dims: [usize; 3]) -> VoxelGrid {
+        let mut g = VoxelGrid::new(Vec3::new(0.0, 0.0, 0.0), dims, 0.5);
+        g.palette[1] = Some(3);
+        g.palette[2] = Some(7);
+        g
+    }
+
+    // Cada bloque ocupado cae adentro de exactamente una caja, del material
+    // que le toca, y las cajas no cubren nada más
+    fn covers_exactly(g: &VoxelGrid, boxes: &[Aabb]) {
+        let [nx, ny, nz] = g.dims;
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let c = g.origin + Vec3::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5) * g.scale;
+                    let inside: Vec<&Aabb> = boxes.iter()
+                        .filter(|b| (0..3).all(|i| comp(b.min, i) < comp(c, i) && comp(c, i) < comp(b.max, i)))
+                        .collect();
+                    match g.get(x, y, z) {
+                        0 => assert!(inside.is_empty(), "bloque vacío cubierto en {x} {y} {z}"),
+                        id => {
+                            assert_eq!(inside.len(), 1, "bloque {x} {y} {z}");
+                            assert_eq!(inside[0].mat_id, g.palette[id as usize].unwrap());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_merges_solid_block() {
+        let mut g = grid([20, 6, 9]);
+        g.apply(VoxelEdit::Fill { id: 1, min: [2, 1, 3], max: [18, 4, 7] });
+        g.finish();
+        let boxes = g.greedy();
+        assert_eq!(boxes.len(), 1);
+        covers_exactly(&g, &boxes);
+    }
+
+    #[test]
+    fn greedy_keeps_materials_apart() {
+        let mut g = grid([8, 4, 4]);
+        g.apply(VoxelEdit::Fill { id: 1, min: [0, 0, 0], max: [3, 3, 3] });
+        g.apply(VoxelEdit::Fill { id: 2, min: [4, 0, 0], max: [7, 3, 3] });
+        g.finish();
+        let boxes = g.greedy();
+        assert_eq!(boxes.len(), 2);
+        assert!(boxes.iter().any(|b| b.mat_id == 3) && boxes.iter().any(|b| b.mat_id == 7));
+        covers_exactly(&g, &boxes);
+    }
+
+    #[test]
+    fn greedy_covers_irregular_shapes() {
+        let mut g = grid([18, 18, 18]);
+        g.apply(VoxelEdit::Terrain { top: 1, under: 2, deep: 1, seed: 5, base: 6.0, height: 5.0, freq: 0.2 });
+        g.apply(VoxelEdit::Fill { id: 0, min: [4, 0, 4], max: [9, 3, 12] });
+        g.finish();
+        let boxes = g.greedy();
+        assert!(boxes.len() < g.count());
+        covers_exactly(&g, &boxes);
+    }
+}
@@ -0,0 +1,36 @@
+// src/watch.rs
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use crate::scene::Scene;
+use crate::scene_file::{self, SceneError};
+
+// Cada cuánto mirar la fecha del archivo
+const POLL: Duration = Duration::from_millis(300);
+
+// Vigila un archivo de escena por fecha de modificación
+pub struct SceneWatcher {
+    path: PathBuf,
+    mtime: Option<SystemTime>,
+    last_check: Instant,
+}
+
+fn mtime(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl SceneWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self { mtime: mtime(&path), path, last_check: Instant::now() }
+    }
+
+    // None si no cambió; si cambió, el resultado de recargarlo
+    pub fn poll(&mut self) -> Option<Result<Scene, SceneError>> {
+        if self.last_check.elapsed() < POLL { return None; }
+        self.last_check = Instant::now();
+        let m = mtime(&self.path);
+        if m == self.mtime { return None; }
+        self.mtime = m;
+        Some(scene_file::load(&self.path))
+    }
+}